@@ -1,6 +1,13 @@
 use std::ffi::OsString;
-use clap::{arg, Command};
-use crate::commands::{decode, encode, remove};
+use std::fs;
+use std::str::FromStr;
+use clap::{arg, value_parser, ArgGroup, ArgMatches, Command};
+use pngyinx::payload::DEFAULT_MAX_SIZE;
+use pngyinx::split::MAX_FRAGMENT_SIZE;
+use pngyinx::strip::{Preset, StripPolicy};
+use pngyinx::ChunkType;
+use crate::commands::{decode, encode, exif_list, exif_remove, icc_embed, icc_extract, inspect, keygen, remove, sign, strip, text_delete, text_get, text_list, text_set, validate, verify, DecodeOptions, EncodeOptions, TextOptions};
+use crate::output::{fail, Format};
 
 fn cli() -> Command {
     Command::new("pngyinx")
@@ -8,52 +15,236 @@ fn cli() -> Command {
         .subcommand_required(true)
         .arg_required_else_help(true)
         .allow_external_subcommands(true)
+        .arg(arg!(--format <FORMAT> "How decode, exif list, inspect, strip, text, validate and verify print their results")
+            .value_parser(["text", "json"])
+            .default_value("text")
+            .global(true))
+        .arg(arg!(--"touch-time" "Record the current time in tIME whenever a command changes the png")
+            .global(true))
         .subcommand(
             Command::new("encode")
                 .about("To encode your secret")
                 .arg(arg!(<PATH> "The path to your png"))
                 .arg(arg!(<CHUNKTYPE> "The secret key"))
-                .arg(arg!(<MESSAGE> "Your secret message"))
+                .arg(arg!([MESSAGE] "Your secret message").required_unless_present("file"))
+                .arg(arg!(--file <FILE> "Embed the content of this file instead of a message").conflicts_with("MESSAGE"))
+                .arg(arg!(--mime <TYPE> "The MIME type of the embedded file").requires("file"))
+                .arg(arg!(--"after-ihdr" "Place the secret right after IHDR instead of before IEND"))
+                .arg(arg!(--compress [LEVEL] "Compress the message with zlib, at level 0 to 9 (6 by default)")
+                    .value_parser(value_parser!(u32).range(0..=9))
+                    .require_equals(true)
+                    .default_missing_value("6"))
+                .arg(arg!(--passphrase <PASSPHRASE> "Encrypt the message with this passphrase"))
+                .arg(arg!(--"passphrase-file" <FILE> "Encrypt the message with the passphrase in this file").conflicts_with("passphrase"))
+                .arg(arg!(--recipient <PUBKEY> "Encrypt the message to this public key, given in hexadecimal or as a file")
+                    .conflicts_with_all(["passphrase", "passphrase-file"]))
+                .arg(arg!(--split <BYTES> "Spread the message over several chunks of at most this size")
+                    .value_parser(value_parser!(usize)))
                 .arg_required_else_help(true)
         ).subcommand(
             Command::new("decode")
                 .about("To decode your secret")
                 .arg(arg!(<PATH> "The path to your png"))
                 .arg(arg!(<CHUNKTYPE> "The secret key"))
+                .arg(arg!(--all "Decode every message stored under this key"))
+                .arg(arg!(--index <N> "Decode the message at this position among those stored under this key, from 0")
+                    .value_parser(value_parser!(usize))
+                    .conflicts_with("all"))
+                .arg(arg!(--output <PATH> "Write the message to this file, numbered when several are decoded, or to this directory under its original name"))
+                .arg(arg!(--"max-size" <BYTES> "The largest size a compressed message may inflate to, 64 MiB by default")
+                    .value_parser(value_parser!(usize)))
+                .arg(arg!(--passphrase <PASSPHRASE> "Decrypt the message with this passphrase"))
+                .arg(arg!(--"passphrase-file" <FILE> "Decrypt the message with the passphrase in this file").conflicts_with("passphrase"))
+                .arg(arg!(--identity <KEYFILE> "Decrypt the message with the secret key in this file")
+                    .conflicts_with_all(["passphrase", "passphrase-file"]))
                 .arg_required_else_help(true)
         ).subcommand(
         Command::new("remove")
             .about("To remove your secret")
             .arg(arg!(<PATH> "The path to your png"))
             .arg(arg!(<CHUNKTYPE> "The secret key"))
+            .arg(arg!(--all "Remove every message stored under this key"))
+            .arg_required_else_help(true)
+    ).subcommand(
+        Command::new("keygen")
+            .about("To generate a keypair to receive secrets")
+            .arg(arg!(<PATH> "Where to write your secret key, the public key goes to PATH.pub"))
+            .arg(arg!(--signing "Generate a key to sign pngs instead of one to receive secrets"))
+            .arg_required_else_help(true)
+    ).subcommand(
+        Command::new("sign")
+            .about("To sign your png")
+            .arg(arg!(<PATH> "The path to your png"))
+            .arg(arg!(--key <KEYFILE> "Your signing key, given in hexadecimal or as a file").required(true))
+            .arg_required_else_help(true)
+    ).subcommand(
+        Command::new("verify")
+            .about("To check the signature of a png")
+            .arg(arg!(<PATH> "The path to your png"))
+            .arg(arg!(--pubkey <PUBKEY> "The public key of the signer, given in hexadecimal or as a file").required(true))
+            .arg_required_else_help(true)
+    ).subcommand(
+        Command::new("text")
+            .about("To read and edit the textual metadata of your png")
+            .subcommand_required(true)
+            .arg_required_else_help(true)
+            .subcommand(
+                Command::new("list")
+                    .about("To list every entry")
+                    .arg(arg!(<PATH> "The path to your png"))
+                    .arg_required_else_help(true)
+            ).subcommand(
+                Command::new("get")
+                    .about("To print the entries with a keyword")
+                    .arg(arg!(<PATH> "The path to your png"))
+                    .arg(arg!(<KEYWORD> "The keyword of the entry"))
+                    .arg_required_else_help(true)
+            ).subcommand(
+                Command::new("set")
+                    .about("To add an entry, replacing those with the same keyword")
+                    .arg(arg!(<PATH> "The path to your png"))
+                    .arg(arg!(<KEYWORD> "The keyword of the entry"))
+                    .arg(arg!(<TEXT> "The text of the entry"))
+                    .arg(arg!(--compress "Compress the text"))
+                    .arg(arg!(--language <TAG> "The language of the text, which stores it as international text"))
+                    .arg(arg!(--"translated-keyword" <KEYWORD> "The keyword in the language of the text, which stores it as international text"))
+                    .arg_required_else_help(true)
+            ).subcommand(
+                Command::new("delete")
+                    .about("To remove the entries with a keyword")
+                    .arg(arg!(<PATH> "The path to your png"))
+                    .arg(arg!(<KEYWORD> "The keyword of the entry"))
+                    .arg_required_else_help(true)
+            )
+    ).subcommand(
+        Command::new("icc")
+            .about("To move ICC color profiles in and out of your png")
+            .subcommand_required(true)
+            .arg_required_else_help(true)
+            .subcommand(
+                Command::new("extract")
+                    .about("To save the embedded ICC profile to a file")
+                    .arg(arg!(<PATH> "The path to your png"))
+                    .arg(arg!(<OUTPUT> "Where to write the profile"))
+                    .arg_required_else_help(true)
+            ).subcommand(
+                Command::new("embed")
+                    .about("To embed an ICC profile, replacing any sRGB chunk")
+                    .arg(arg!(<PATH> "The path to your png"))
+                    .arg(arg!(<PROFILE> "The path to the ICC profile"))
+                    .arg(arg!(--name <NAME> "The name of the profile").default_value("ICC profile"))
+                    .arg_required_else_help(true)
+            )
+    ).subcommand(
+        Command::new("exif")
+            .about("To read and clean the EXIF metadata of your png")
+            .subcommand_required(true)
+            .arg_required_else_help(true)
+            .subcommand(
+                Command::new("list")
+                    .about("To list every EXIF tag")
+                    .arg(arg!(<PATH> "The path to your png"))
+                    .arg_required_else_help(true)
+            ).subcommand(
+                Command::new("remove")
+                    .about("To remove the EXIF metadata")
+                    .arg(arg!(<PATH> "The path to your png"))
+                    .arg(arg!(--gps "Only remove the GPS tags"))
+                    .arg_required_else_help(true)
+            )
+    ).subcommand(
+        Command::new("strip")
+            .about("To remove metadata chunks before publishing your png")
+            .arg(arg!(<PATH> "The path to your png"))
+            .arg(arg!(--preset <PRESET> "Strip every ancillary chunk, the privacy-sensitive ones (eXIf, text and tIME) or the private ones")
+                .value_parser(["ancillary", "privacy", "private"]))
+            .arg(arg!(--keep <TYPES> "Chunk types to keep whatever the preset, separated by commas")
+                .value_parser(chunk_type)
+                .value_delimiter(','))
+            .arg(arg!(--drop <TYPES> "Chunk types to strip as well, separated by commas")
+                .value_parser(chunk_type)
+                .value_delimiter(','))
+            .group(ArgGroup::new("policy").args(["preset", "drop"]).multiple(true).required(true))
+            .arg_required_else_help(true)
+    ).subcommand(
+        Command::new("inspect")
+            .about("To list the chunks of your png")
+            .arg(arg!(<PATH> "The path to your png"))
+            .arg_required_else_help(true)
+    ).subcommand(
+        Command::new("validate")
+            .about("To check that your png follows the specification")
+            .arg(arg!(<PATH> "The path to your png"))
             .arg_required_else_help(true)
     )
 }
 
+fn passphrase(sub_matches: &ArgMatches, format: Format) -> Option<String> {
+    if let Some(passphrase) = sub_matches.get_one::<String>("passphrase") {
+        return Some(passphrase.clone());
+    }
+    sub_matches.get_one::<String>("passphrase-file").map(|path| {
+        let passphrase = match fs::read_to_string(path) {
+            Ok(passphrase) => passphrase,
+            Err(e) => fail(format, e.into(), "Unable to read the passphrase file"),
+        };
+        passphrase.trim_end_matches(['\r', '\n']).to_string()
+    })
+}
+
+fn chunk_type(value: &str) -> Result<ChunkType, String> {
+    ChunkType::from_str(value).map_err(|e| e.to_string())
+}
+
 pub fn args_processing() {
     let matches = cli().get_matches();
+    let format = match matches.get_one::<String>("format").map(String::as_str) {
+        Some("json") => Format::Json,
+        _ => Format::Text,
+    };
+    let touch_time = matches.get_flag("touch-time");
     
     match matches.subcommand() { 
         Some(("encode", sub_matches)) => {
             let path = sub_matches.get_one::<String>("PATH").expect("required");
             let key = sub_matches.get_one::<String>("CHUNKTYPE").expect("required");
-            let message = sub_matches.get_one::<String>("MESSAGE").expect("required");
+            let message = sub_matches.get_one::<String>("MESSAGE");
             println!(
                 "Encoding your secret from {} with key {}",
                 path,
                 key,
             );
-            encode(path, key, message);
+            let options = EncodeOptions {
+                after_ihdr: sub_matches.get_flag("after-ihdr"),
+                file: sub_matches.get_one::<String>("file").cloned(),
+                mime: sub_matches.get_one::<String>("mime").cloned(),
+                compression: sub_matches.get_one::<u32>("compress").copied(),
+                passphrase: passphrase(sub_matches, format),
+                recipient: sub_matches.get_one::<String>("recipient").cloned(),
+                split: sub_matches.get_one::<usize>("split").copied().unwrap_or(MAX_FRAGMENT_SIZE),
+                touch_time,
+            };
+            encode(path, key, message.map(String::as_str), &options);
         },
         Some(("decode", sub_matches)) => {
             let path = sub_matches.get_one::<String>("PATH").expect("required");
             let key = sub_matches.get_one::<String>("CHUNKTYPE").expect("required");
-            println!(
-                "Decoding your secret from {} with key {}",
-                path,
-                key,
-            );
-            decode(path, key);
+            if format == Format::Text {
+                println!(
+                    "Decoding your secret from {} with key {}",
+                    path,
+                    key,
+                );
+            }
+            let options = DecodeOptions {
+                all: sub_matches.get_flag("all"),
+                index: sub_matches.get_one::<usize>("index").copied(),
+                output: sub_matches.get_one::<String>("output").cloned(),
+                max_size: sub_matches.get_one::<usize>("max-size").copied().unwrap_or(DEFAULT_MAX_SIZE),
+                passphrase: passphrase(sub_matches, format),
+                identity: sub_matches.get_one::<String>("identity").cloned(),
+            };
+            decode(path, key, &options, format);
         },
         Some(("remove", sub_matches)) => {
             let path = sub_matches.get_one::<String>("PATH").expect("required");
@@ -63,7 +254,79 @@ pub fn args_processing() {
                 path,
                 key,
             );
-            remove(path, key);
+            remove(path, key, sub_matches.get_flag("all"), touch_time);
+        },
+        Some(("keygen", sub_matches)) => {
+            let path = sub_matches.get_one::<String>("PATH").expect("required");
+            keygen(path, sub_matches.get_flag("signing"));
+        },
+        Some(("sign", sub_matches)) => {
+            let path = sub_matches.get_one::<String>("PATH").expect("required");
+            let key = sub_matches.get_one::<String>("key").expect("required");
+            sign(path, key, touch_time);
+        },
+        Some(("verify", sub_matches)) => {
+            let path = sub_matches.get_one::<String>("PATH").expect("required");
+            let public_key = sub_matches.get_one::<String>("pubkey").expect("required");
+            verify(path, public_key, format);
+        },
+        Some(("text", sub_matches)) => {
+            let (command, sub_matches) = sub_matches.subcommand().expect("required");
+            let path = sub_matches.get_one::<String>("PATH").expect("required");
+            let keyword = sub_matches.try_get_one::<String>("KEYWORD").ok().flatten();
+            match command {
+                "list" => text_list(path, format),
+                "get" => text_get(path, keyword.expect("required"), format),
+                "set" => {
+                    let options = TextOptions {
+                        compress: sub_matches.get_flag("compress"),
+                        language: sub_matches.get_one::<String>("language").cloned(),
+                        translated_keyword: sub_matches.get_one::<String>("translated-keyword").cloned(),
+                    };
+                    let text = sub_matches.get_one::<String>("TEXT").expect("required");
+                    text_set(path, keyword.expect("required"), text, &options, touch_time);
+                },
+                _ => text_delete(path, keyword.expect("required"), touch_time),
+            }
+        },
+        Some(("icc", sub_matches)) => {
+            let (command, sub_matches) = sub_matches.subcommand().expect("required");
+            let path = sub_matches.get_one::<String>("PATH").expect("required");
+            match command {
+                "extract" => icc_extract(path, sub_matches.get_one::<String>("OUTPUT").expect("required")),
+                _ => {
+                    let profile = sub_matches.get_one::<String>("PROFILE").expect("required");
+                    let name = sub_matches.get_one::<String>("name").expect("default");
+                    icc_embed(path, profile, name, touch_time);
+                },
+            }
+        },
+        Some(("exif", sub_matches)) => {
+            let (command, sub_matches) = sub_matches.subcommand().expect("required");
+            let path = sub_matches.get_one::<String>("PATH").expect("required");
+            match command {
+                "list" => exif_list(path, format),
+                _ => exif_remove(path, sub_matches.get_flag("gps"), touch_time),
+            }
+        },
+        Some(("strip", sub_matches)) => {
+            let path = sub_matches.get_one::<String>("PATH").expect("required");
+            let preset = sub_matches.get_one::<String>("preset").map(|preset| match preset.as_str() {
+                "ancillary" => Preset::Ancillary,
+                "privacy" => Preset::Privacy,
+                _ => Preset::Private,
+            });
+            let chunk_types = |id| sub_matches.get_many::<ChunkType>(id).into_iter().flatten().copied().collect();
+            let policy = StripPolicy { preset, keep: chunk_types("keep"), drop: chunk_types("drop") };
+            strip(path, &policy, format, touch_time);
+        },
+        Some(("inspect", sub_matches)) => {
+            let path = sub_matches.get_one::<String>("PATH").expect("required");
+            inspect(path, format);
+        },
+        Some(("validate", sub_matches)) => {
+            let path = sub_matches.get_one::<String>("PATH").expect("required");
+            validate(path, format);
         },
         Some((ext, sub_matches)) => {
             let args = sub_matches
@@ -1,10 +1,14 @@
 use std::fmt::{Display, Formatter};
+use std::io::Write;
 use std::str::from_utf8;
-use crate::chunk::ChunkError::{InvalidChecksum, DataToStringError};
+use crate::chunk::ChunkError::{DataPastEnd, DataTooLong, DataToStringError, InvalidChecksum, InvalidChunkType, LengthTooLarge, TruncatedCrc, TruncatedLength, TruncatedType};
+use crate::chunk_ref::ChunkRef;
 use crate::chunk_type::ChunkType;
 use crate::Error;
 use crate::Result;
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Chunk {
     length: u32,
     chunk_type: ChunkType,
@@ -16,38 +20,47 @@ impl TryFrom<&[u8]> for Chunk {
     type Error = Error;
 
     fn try_from(value: &[u8]) -> Result<Self> {
-        let (int_bytes, rest) = value.split_at(std::mem::size_of::<u32>());
-        let length = u32::from_be_bytes(int_bytes.try_into().unwrap());
-        let (chunk_type_slice, rest) = rest.split_at(4);
-        let chunk_type_slice: [u8;4] = chunk_type_slice.try_into().unwrap();
-        let chunk_type = ChunkType::try_from(chunk_type_slice).unwrap();
-        let (data_slice, rest) = rest.split_at(length as usize);
-        let data = data_slice.to_vec();
-        let (crc_bytes, _useless) = rest.split_at(std::mem::size_of::<u32>());
-        let crc = u32::from_be_bytes(crc_bytes.try_into().unwrap());
-        let checksum = crc32fast::hash(&[&chunk_type.bytes(), data.as_slice()].concat());
-        if crc == checksum {
-            Ok(Self {
-                length,
-                chunk_type,
-                data,
-                crc,
-            })
-        } else {
-            Err(Box::new(InvalidChecksum(crc, checksum)))
-        }
+        Chunk::from_bytes_at(value, 0)
     }
 }
 
 impl Display for Chunk {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", from_utf8(self.data()).unwrap())
+        write!(f, "{}", String::from_utf8_lossy(self.data()))
     }
 }
 
 impl Chunk {
+    pub const MAX_LENGTH: u32 = (1 << 31) - 1;
+
+    /// Panics if `data` is longer than `MAX_LENGTH`, see `try_new` for a fallible version.
     pub fn new(chunk_type: ChunkType, data: Vec<u8>) -> Chunk {
-        let crc = crc32fast::hash(&[&chunk_type.bytes(), data.as_slice()].concat());
+        Chunk::try_new(chunk_type, data).expect("chunk data is longer than Chunk::MAX_LENGTH")
+    }
+
+    pub fn try_new(chunk_type: ChunkType, data: Vec<u8>) -> Result<Chunk> {
+        if data.len() > Chunk::MAX_LENGTH as usize {
+            return Err(DataTooLong(data.len()).into());
+        }
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(&chunk_type.bytes());
+        hasher.update(&data);
+        let crc = hasher.finalize();
+        Ok(Chunk {
+            length: data.len() as u32,
+            chunk_type,
+            data,
+            crc,
+        })
+    }
+
+    /// Parses the chunk at the start of `value`, `offset` being the absolute position of
+    /// `value` in the file so that errors can point at the faulty chunk.
+    pub fn from_bytes_at(value: &[u8], offset: usize) -> Result<Chunk> {
+        ChunkRef::from_bytes_at(value, offset).map(|chunk| chunk.to_chunk())
+    }
+
+    pub(crate) fn from_parts(chunk_type: ChunkType, data: Vec<u8>, crc: u32) -> Chunk {
         Chunk {
             length: data.len() as u32,
             chunk_type,
@@ -64,11 +77,11 @@ impl Chunk {
         &self.chunk_type
     }
 
-    fn data(&self) -> &[u8] {
+    pub fn data(&self) -> &[u8] {
         self.data.as_slice()
     }
 
-    fn crc(&self) -> u32 {
+    pub fn crc(&self) -> u32 {
         self.crc
     }
 
@@ -76,7 +89,7 @@ impl Chunk {
         let string = from_utf8(self.data.as_slice());
         match string {
             Ok(text) => Ok(text.to_string()),
-            Err(_) => Err(Box::new(DataToStringError)),
+            Err(_) => Err(DataToStringError.into()),
         }
     }
 
@@ -91,19 +104,58 @@ impl Chunk {
             .collect();
         result
     }
+
+    pub fn write_to(&self, writer: &mut impl Write) -> Result<()> {
+        writer.write_all(&self.length.to_be_bytes())?;
+        writer.write_all(&self.chunk_type.bytes())?;
+        writer.write_all(&self.data)?;
+        writer.write_all(&self.crc.to_be_bytes())?;
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
 pub enum ChunkError {
     DataToStringError,
-    InvalidChecksum(u32, u32)
+    TruncatedLength(usize),
+    LengthTooLarge(usize, u32),
+    TruncatedType(usize),
+    InvalidChunkType(usize, [u8; 4]),
+    DataPastEnd(usize, u32, usize),
+    TruncatedCrc(usize),
+    InvalidChecksum(usize, u32, u32),
+    DataTooLong(usize),
 }
 
 impl Display for ChunkError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             DataToStringError => write!(f, "An error occurred while trying to parse the data as a string."),
-            InvalidChecksum(expected, found) => write!(f, "Incorrect checksum, expected: {} but found: {}", expected, found),
+            TruncatedLength(offset) => write!(f, "Chunk at offset {}: the length field is truncated", offset),
+            LengthTooLarge(offset, length) => write!(f, "Chunk at offset {}: declared length {} exceeds the maximum of 2^31-1", offset, length),
+            TruncatedType(offset) => write!(f, "Chunk at offset {}: the chunk type is truncated", offset),
+            InvalidChunkType(offset, bytes) => write!(f, "Chunk at offset {}: invalid chunk type bytes {:?}", offset, bytes),
+            DataPastEnd(offset, declared, available) => write!(f, "Chunk at offset {}: declared length {} goes past the end of the input ({} bytes available)", offset, declared, available),
+            TruncatedCrc(offset) => write!(f, "Chunk at offset {}: the CRC is truncated", offset),
+            InvalidChecksum(offset, expected, found) => write!(f, "Chunk at offset {}: incorrect checksum, expected: {} but found: {}", offset, expected, found),
+            DataTooLong(length) => write!(f, "{} bytes of data don't fit in a chunk, the maximum is 2^31-1", length),
+        }
+    }
+}
+
+impl ChunkError {
+    /// A stable identifier for the error, for machine-readable output.
+    pub fn code(&self) -> &'static str {
+        match self {
+            DataToStringError => "data_to_string",
+            TruncatedLength(_) => "truncated_length",
+            LengthTooLarge(_, _) => "length_too_large",
+            TruncatedType(_) => "truncated_type",
+            InvalidChunkType(_, _) => "invalid_chunk_type",
+            DataPastEnd(_, _, _) => "data_past_end",
+            TruncatedCrc(_) => "truncated_crc",
+            InvalidChecksum(_, _, _) => "invalid_checksum",
+            DataTooLong(_) => "data_too_long",
         }
     }
 }
@@ -114,6 +166,7 @@ impl std::error::Error for ChunkError {}
 mod tests {
     use super::*;
     use crate::chunk_type::ChunkType;
+    use crate::PngyinxError;
     use std::str::FromStr;
 
     fn testing_chunk() -> Chunk {
@@ -217,6 +270,62 @@ mod tests {
         assert!(chunk.is_err());
     }
 
+    #[test]
+    fn test_chunk_write_to() {
+        let chunk = testing_chunk();
+        let mut bytes = Vec::new();
+        chunk.write_to(&mut bytes).unwrap();
+        assert_eq!(bytes, chunk.as_bytes());
+    }
+
+    #[test]
+    fn test_truncated_length_from_bytes() {
+        let chunk = Chunk::from_bytes_at(&[0, 0], 33);
+        assert!(matches!(chunk, Err(PngyinxError::Chunk(TruncatedLength(33)))));
+    }
+
+    #[test]
+    fn test_length_past_end_from_bytes() {
+        let chunk_data: Vec<u8> = 1000u32
+            .to_be_bytes()
+            .iter()
+            .chain("RuSt".as_bytes().iter())
+            .chain("short".as_bytes().iter())
+            .copied()
+            .collect();
+
+        let chunk = Chunk::from_bytes_at(chunk_data.as_ref(), 8);
+        assert!(matches!(chunk, Err(PngyinxError::Chunk(DataPastEnd(8, 1000, 5)))));
+    }
+
+    #[test]
+    fn test_invalid_chunk_type_from_bytes() {
+        let chunk_data: Vec<u8> = 0u32
+            .to_be_bytes()
+            .iter()
+            .chain([82, 117, 49, 116].iter())
+            .chain(0u32.to_be_bytes().iter())
+            .copied()
+            .collect();
+
+        let chunk = Chunk::from_bytes_at(chunk_data.as_ref(), 12);
+        assert!(matches!(chunk, Err(PngyinxError::Chunk(InvalidChunkType(12, [82, 117, 49, 116])))));
+    }
+
+    #[test]
+    fn test_truncated_crc_from_bytes() {
+        let chunk_data: Vec<u8> = 0u32
+            .to_be_bytes()
+            .iter()
+            .chain("RuSt".as_bytes().iter())
+            .chain([1, 2].iter())
+            .copied()
+            .collect();
+
+        let chunk = Chunk::try_from(chunk_data.as_ref());
+        assert!(matches!(chunk, Err(PngyinxError::Chunk(TruncatedCrc(0)))));
+    }
+
     #[test]
     pub fn test_chunk_trait_impls() {
         let data_length: u32 = 42;
@@ -0,0 +1,141 @@
+use crate::chunk::Chunk;
+use crate::chunk::ChunkError::{DataPastEnd, InvalidChecksum, InvalidChunkType, LengthTooLarge, TruncatedCrc, TruncatedLength, TruncatedType};
+use crate::chunk_type::ChunkType;
+use crate::Error;
+use crate::Result;
+
+/// A chunk borrowed straight from the bytes of a png, without copying its data.
+#[derive(Clone, Copy, Debug)]
+pub struct ChunkRef<'a> {
+    offset: usize,
+    chunk_type: ChunkType,
+    bytes: &'a [u8],
+}
+
+impl<'a> TryFrom<&'a [u8]> for ChunkRef<'a> {
+    type Error = Error;
+
+    fn try_from(value: &'a [u8]) -> Result<Self> {
+        ChunkRef::from_bytes_at(value, 0)
+    }
+}
+
+impl<'a> ChunkRef<'a> {
+    /// Parses the chunk at the start of `value`, `offset` being the absolute position of
+    /// `value` in the file so that errors can point at the faulty chunk.
+    pub fn from_bytes_at(value: &'a [u8], offset: usize) -> Result<ChunkRef<'a>> {
+        let chunk = ChunkRef::from_bytes_at_unchecked(value, offset)?;
+        let checksum = chunk.computed_crc();
+        if chunk.crc() == checksum {
+            Ok(chunk)
+        } else {
+            Err(InvalidChecksum(offset, chunk.crc(), checksum).into())
+        }
+    }
+
+    /// Like `from_bytes_at` but accepts a chunk whose stored CRC is wrong, to look at damaged files.
+    pub fn from_bytes_at_unchecked(value: &'a [u8], offset: usize) -> Result<ChunkRef<'a>> {
+        let length_bytes = value.get(0..4).ok_or(TruncatedLength(offset))?;
+        let length = u32::from_be_bytes(length_bytes.try_into().unwrap());
+        if length > Chunk::MAX_LENGTH {
+            return Err(LengthTooLarge(offset, length).into());
+        }
+        let chunk_type_bytes: [u8; 4] = value.get(4..8).ok_or(TruncatedType(offset))?.try_into().unwrap();
+        let chunk_type = ChunkType::try_from(chunk_type_bytes)
+            .map_err(|_| InvalidChunkType(offset, chunk_type_bytes))?;
+        let data_end = 8 + length as usize;
+        value.get(8..data_end).ok_or(DataPastEnd(offset, length, value.len() - 8))?;
+        value.get(data_end..data_end + 4).ok_or(TruncatedCrc(offset))?;
+        Ok(Self {
+            offset,
+            chunk_type,
+            bytes: &value[..data_end + 4],
+        })
+    }
+
+    /// The absolute position of the chunk in the file it was parsed from.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn length(&self) -> u32 {
+        (self.bytes.len() - 12) as u32
+    }
+
+    pub fn chunk_type(&self) -> &ChunkType {
+        &self.chunk_type
+    }
+
+    pub fn data(&self) -> &'a [u8] {
+        &self.bytes[8..self.bytes.len() - 4]
+    }
+
+    pub fn crc(&self) -> u32 {
+        u32::from_be_bytes(self.bytes[self.bytes.len() - 4..].try_into().unwrap())
+    }
+
+    /// The CRC of the type and data, which `crc` matches unless the chunk is damaged.
+    pub fn computed_crc(&self) -> u32 {
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(&self.bytes[4..self.bytes.len() - 4]);
+        hasher.finalize()
+    }
+
+    /// The whole chunk as it appears in the file: length, type, data and CRC.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    pub fn to_chunk(&self) -> Chunk {
+        Chunk::from_parts(self.chunk_type, self.data().to_vec(), self.crc())
+    }
+}
+
+impl From<ChunkRef<'_>> for Chunk {
+    fn from(value: ChunkRef<'_>) -> Self {
+        value.to_chunk()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::ChunkError;
+    use crate::PngyinxError;
+    use std::str::FromStr;
+
+    fn testing_chunk_bytes() -> Vec<u8> {
+        Chunk::new(ChunkType::from_str("RuSt").unwrap(), b"This is where your secret message will be!".to_vec()).as_bytes()
+    }
+
+    #[test]
+    fn test_chunk_ref_from_bytes() {
+        let bytes = testing_chunk_bytes();
+        let chunk = ChunkRef::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(chunk.length(), 42);
+        assert_eq!(chunk.chunk_type().to_string(), "RuSt");
+        assert_eq!(chunk.data(), b"This is where your secret message will be!");
+        assert_eq!(chunk.crc(), 2882656334);
+        assert_eq!(chunk.as_bytes(), bytes.as_slice());
+    }
+
+    #[test]
+    fn test_chunk_ref_to_chunk() {
+        let bytes = testing_chunk_bytes();
+        let chunk: Chunk = ChunkRef::try_from(bytes.as_slice()).unwrap().into();
+        assert_eq!(chunk.as_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_chunk_ref_invalid_crc() {
+        let mut bytes = testing_chunk_bytes();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        let chunk = ChunkRef::from_bytes_at(bytes.as_slice(), 8);
+        assert!(matches!(chunk, Err(PngyinxError::Chunk(ChunkError::InvalidChecksum(8, _, _)))));
+
+        let chunk = ChunkRef::from_bytes_at_unchecked(bytes.as_slice(), 8).unwrap();
+        assert_eq!(chunk.crc(), 2882656334 ^ 1);
+        assert_eq!(chunk.computed_crc(), 2882656334);
+    }
+}
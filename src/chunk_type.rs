@@ -5,7 +5,7 @@ use crate::chunk_type::ChunkTypeDecodingError::{BadByte, BadLength};
 use crate::Error;
 use crate::Result;
 
-#[derive(PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub struct ChunkType {
     chunk_type: [u8; 4],
 }
@@ -28,7 +28,7 @@ impl FromStr for ChunkType {
     fn from_str(s: &str) -> Result<Self> {
         let values = s.as_bytes();
         if s.len() != 4 {
-            return Err(Box::new(BadLength(s.len())))
+            return Err(BadLength(s.len()).into())
         }
         let chunk = ChunkType {chunk_type: [values[0], values[1], values[2], values[3]]};
         match chunk.is_valid_for_creation() {
@@ -44,12 +44,20 @@ impl Display for ChunkType {
     }
 }
 
+/// Serialized as its four letters, e.g. `"IHDR"`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for ChunkType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
 impl ChunkType {
     pub fn bytes(&self) -> [u8; 4] {
         self.chunk_type
     }
 
-    fn is_valid(&self) -> bool {
+    pub fn is_valid(&self) -> bool {
         for value in self.chunk_type {
             if !value.is_ascii_alphabetic() {
                 return false;
@@ -64,25 +72,25 @@ impl ChunkType {
     fn is_valid_for_creation(&self) -> Result<bool> {
         for value in self.chunk_type {
             if !value.is_ascii_alphabetic() {
-                return Err(Box::new(BadByte(value)));
+                return Err(BadByte(value).into());
             }
         }
         Ok(true)
     }
 
-    fn is_critical(&self) -> bool {
+    pub fn is_critical(&self) -> bool {
         self.chunk_type[0] & 0b00100000 == 0
     }
 
-    fn is_public(&self) -> bool {
+    pub fn is_public(&self) -> bool {
         self.chunk_type[1] & 0b00100000 == 0
     }
 
-    fn is_reserved_bit_valid(&self) -> bool {
+    pub fn is_reserved_bit_valid(&self) -> bool {
         self.chunk_type[2] & 0b00100000 == 0
     }
 
-    fn is_safe_to_copy(&self) -> bool {
+    pub fn is_safe_to_copy(&self) -> bool {
         self.chunk_type[3] & 0b00100000 == 32
     }
 }
@@ -102,6 +110,16 @@ impl Display for ChunkTypeDecodingError {
     }
 }
 
+impl ChunkTypeDecodingError {
+    /// A stable identifier for the error, for machine-readable output.
+    pub fn code(&self) -> &'static str {
+        match self {
+            BadByte(_) => "bad_byte",
+            BadLength(_) => "bad_length",
+        }
+    }
+}
+
 impl std::error::Error for ChunkTypeDecodingError {}
 
 #[cfg(test)]
@@ -0,0 +1,440 @@
+use std::fmt::{Display, Formatter};
+use std::io::{Read, Write};
+use std::str::FromStr;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::color::ColorError::{DecompressionFailed, InvalidSignificantBits, NotAllowed, PaletteIndexTooLarge, SampleTooLarge, UnknownCompressionMethod, UnknownRenderingIntent, WrongLength, ZeroGamma};
+use crate::ihdr::{ColorType, Ihdr};
+use crate::palette::Palette;
+use crate::text::check_keyword;
+use crate::Result;
+
+pub const GAMA_CHUNK_TYPE: &str = "gAMA";
+pub const CHRM_CHUNK_TYPE: &str = "cHRM";
+pub const SRGB_CHUNK_TYPE: &str = "sRGB";
+pub const ICCP_CHUNK_TYPE: &str = "iCCP";
+pub const SBIT_CHUNK_TYPE: &str = "sBIT";
+pub const BKGD_CHUNK_TYPE: &str = "bKGD";
+
+/// The largest ICC profile that is decompressed, against zlib bombs.
+pub const MAX_PROFILE_SIZE: usize = 16 * 1024 * 1024;
+
+/// The gamma of the image, from gAMA, times 100000.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Gamma {
+    pub gamma: u32,
+}
+
+impl Gamma {
+    pub fn from_data(data: &[u8]) -> Result<Gamma> {
+        let gamma = Gamma { gamma: read_u32(GAMA_CHUNK_TYPE, data, 4)?[0] };
+        gamma.check()?;
+        Ok(gamma)
+    }
+
+    pub fn check(&self) -> Result<()> {
+        if self.gamma == 0 {
+            return Err(ZeroGamma.into());
+        }
+        Ok(())
+    }
+
+    /// The gamma as a number, e.g. 0.45455.
+    pub fn value(&self) -> f64 {
+        self.gamma as f64 / 100000.0
+    }
+
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        self.check()?;
+        Ok(Chunk::new(ChunkType::from_str(GAMA_CHUNK_TYPE)?, self.gamma.to_be_bytes().to_vec()))
+    }
+}
+
+/// The chromaticities of the white point and the primaries, from cHRM, each times 100000.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Chromaticities {
+    pub white_x: u32,
+    pub white_y: u32,
+    pub red_x: u32,
+    pub red_y: u32,
+    pub green_x: u32,
+    pub green_y: u32,
+    pub blue_x: u32,
+    pub blue_y: u32,
+}
+
+impl Chromaticities {
+    pub fn from_data(data: &[u8]) -> Result<Chromaticities> {
+        let values = read_u32(CHRM_CHUNK_TYPE, data, 32)?;
+        Ok(Chromaticities {
+            white_x: values[0],
+            white_y: values[1],
+            red_x: values[2],
+            red_y: values[3],
+            green_x: values[4],
+            green_y: values[5],
+            blue_x: values[6],
+            blue_y: values[7],
+        })
+    }
+
+    pub fn to_data(&self) -> Vec<u8> {
+        [self.white_x, self.white_y, self.red_x, self.red_y, self.green_x, self.green_y, self.blue_x, self.blue_y]
+            .iter()
+            .flat_map(|value| value.to_be_bytes())
+            .collect()
+    }
+
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        Ok(Chunk::new(ChunkType::from_str(CHRM_CHUNK_TYPE)?, self.to_data()))
+    }
+}
+
+/// How colors outside the sRGB gamut are handled, from sRGB, whose presence means the image is sRGB.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "snake_case"))]
+pub enum RenderingIntent {
+    Perceptual = 0,
+    RelativeColorimetric = 1,
+    Saturation = 2,
+    AbsoluteColorimetric = 3,
+}
+
+impl Display for RenderingIntent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RenderingIntent::Perceptual => write!(f, "perceptual"),
+            RenderingIntent::RelativeColorimetric => write!(f, "relative colorimetric"),
+            RenderingIntent::Saturation => write!(f, "saturation"),
+            RenderingIntent::AbsoluteColorimetric => write!(f, "absolute colorimetric"),
+        }
+    }
+}
+
+impl RenderingIntent {
+    pub fn from_data(data: &[u8]) -> Result<RenderingIntent> {
+        match data {
+            [0] => Ok(RenderingIntent::Perceptual),
+            [1] => Ok(RenderingIntent::RelativeColorimetric),
+            [2] => Ok(RenderingIntent::Saturation),
+            [3] => Ok(RenderingIntent::AbsoluteColorimetric),
+            [intent] => Err(UnknownRenderingIntent(*intent).into()),
+            _ => Err(WrongLength(SRGB_CHUNK_TYPE, data.len()).into()),
+        }
+    }
+
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        Ok(Chunk::new(ChunkType::from_str(SRGB_CHUNK_TYPE)?, vec![*self as u8]))
+    }
+}
+
+/// An embedded ICC profile, from iCCP. The profile is kept decompressed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct IccProfile {
+    /// Follows the rules of text keywords.
+    pub name: String,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub profile: Vec<u8>,
+}
+
+impl IccProfile {
+    pub fn new(name: &str, profile: Vec<u8>) -> Result<IccProfile> {
+        check_keyword(name)?;
+        Ok(IccProfile { name: name.to_string(), profile })
+    }
+
+    pub fn from_data(data: &[u8]) -> Result<IccProfile> {
+        let separator = data.iter().position(|&byte| byte == 0).ok_or(WrongLength(ICCP_CHUNK_TYPE, data.len()))?;
+        let name: String = data[..separator].iter().map(|&byte| byte as char).collect();
+        let Some(&method) = data.get(separator + 1) else {
+            return Err(WrongLength(ICCP_CHUNK_TYPE, data.len()).into());
+        };
+        if method != 0 {
+            return Err(UnknownCompressionMethod(method).into());
+        }
+        IccProfile::new(&name, inflate(&data[separator + 2..])?)
+    }
+
+    pub fn to_data(&self) -> Result<Vec<u8>> {
+        check_keyword(&self.name)?;
+        // Keywords are Latin-1, checked above.
+        let mut data: Vec<u8> = self.name.chars().map(|c| c as u8).collect();
+        data.extend_from_slice(&[0, 0]);
+        let mut encoder = ZlibEncoder::new(data, Compression::default());
+        encoder.write_all(&self.profile)?;
+        Ok(encoder.finish()?)
+    }
+
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        Chunk::try_new(ChunkType::from_str(ICCP_CHUNK_TYPE)?, self.to_data()?)
+    }
+}
+
+/// The number of significant bits in each channel of the original image, from sBIT. Indexed
+/// images give them for the red, green and blue channels of the palette.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SignificantBits {
+    pub bits: Vec<u8>,
+}
+
+impl SignificantBits {
+    pub fn from_data(data: &[u8], ihdr: &Ihdr) -> Result<SignificantBits> {
+        let significant_bits = SignificantBits { bits: data.to_vec() };
+        significant_bits.check(ihdr)?;
+        Ok(significant_bits)
+    }
+
+    /// Checks that there is one value per channel of the color type of `ihdr`, each between 1 and
+    /// the sample depth.
+    pub fn check(&self, ihdr: &Ihdr) -> Result<()> {
+        let (channels, sample_depth) = match ihdr.color_type {
+            ColorType::Indexed => (3, 8),
+            color_type => (color_type.channels() as usize, ihdr.bit_depth),
+        };
+        if self.bits.len() != channels {
+            return Err(WrongLength(SBIT_CHUNK_TYPE, self.bits.len()).into());
+        }
+        if let Some(&bits) = self.bits.iter().find(|&&bits| bits == 0 || bits > sample_depth) {
+            return Err(InvalidSignificantBits(bits, sample_depth).into());
+        }
+        Ok(())
+    }
+
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        Ok(Chunk::new(ChunkType::from_str(SBIT_CHUNK_TYPE)?, self.bits.clone()))
+    }
+}
+
+/// The color to show the image against, from bKGD: a palette index, a gray level or a color.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "snake_case"))]
+pub enum Background {
+    Indexed(u8),
+    Grayscale(u16),
+    Truecolor(u16, u16, u16),
+}
+
+impl Background {
+    /// Parses the data of a bKGD chunk, whose layout depends on the color type of the image.
+    pub fn from_data(data: &[u8], ihdr: &Ihdr) -> Result<Background> {
+        let sample = |index: usize| u16::from_be_bytes([data[index], data[index + 1]]);
+        let background = match (ihdr.color_type, data.len()) {
+            (ColorType::Indexed, 1) => Background::Indexed(data[0]),
+            (ColorType::Grayscale | ColorType::GrayscaleAlpha, 2) => Background::Grayscale(sample(0)),
+            (ColorType::Truecolor | ColorType::TruecolorAlpha, 6) => Background::Truecolor(sample(0), sample(2), sample(4)),
+            _ => return Err(WrongLength(BKGD_CHUNK_TYPE, data.len()).into()),
+        };
+        background.check(ihdr, None)?;
+        Ok(background)
+    }
+
+    /// Checks that the background matches the color type and bit depth of `ihdr`, and that its
+    /// index is within `palette`.
+    pub fn check(&self, ihdr: &Ihdr, palette: Option<&Palette>) -> Result<()> {
+        ihdr.check()?;
+        let max_sample = (1u32 << ihdr.bit_depth) - 1;
+        let samples = match (self, ihdr.color_type) {
+            (Background::Indexed(index), ColorType::Indexed) => {
+                let entries = palette.map_or(1 << ihdr.bit_depth, Palette::len);
+                if *index as usize >= entries {
+                    return Err(PaletteIndexTooLarge(*index, entries).into());
+                }
+                Vec::new()
+            },
+            (Background::Grayscale(gray), ColorType::Grayscale | ColorType::GrayscaleAlpha) => vec![*gray],
+            (Background::Truecolor(red, green, blue), ColorType::Truecolor | ColorType::TruecolorAlpha) => vec![*red, *green, *blue],
+            _ => return Err(NotAllowed(BKGD_CHUNK_TYPE, ihdr.color_type).into()),
+        };
+        if let Some(sample) = samples.into_iter().find(|&sample| sample as u32 > max_sample) {
+            return Err(SampleTooLarge(sample, ihdr.bit_depth).into());
+        }
+        Ok(())
+    }
+
+    pub fn to_data(&self) -> Vec<u8> {
+        match self {
+            Background::Indexed(index) => vec![*index],
+            Background::Grayscale(gray) => gray.to_be_bytes().to_vec(),
+            Background::Truecolor(red, green, blue) => [red.to_be_bytes(), green.to_be_bytes(), blue.to_be_bytes()].concat(),
+        }
+    }
+
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        Ok(Chunk::new(ChunkType::from_str(BKGD_CHUNK_TYPE)?, self.to_data()))
+    }
+}
+
+fn read_u32(chunk_type: &'static str, data: &[u8], length: usize) -> Result<Vec<u32>> {
+    if data.len() != length {
+        return Err(WrongLength(chunk_type, data.len()).into());
+    }
+    Ok(data.chunks(4).map(|bytes| u32::from_be_bytes(bytes.try_into().unwrap())).collect())
+}
+
+fn inflate(data: &[u8]) -> Result<Vec<u8>> {
+    let mut profile = Vec::new();
+    ZlibDecoder::new(data)
+        .take(MAX_PROFILE_SIZE as u64 + 1)
+        .read_to_end(&mut profile)
+        .map_err(|_| DecompressionFailed)?;
+    if profile.len() > MAX_PROFILE_SIZE {
+        return Err(DecompressionFailed.into());
+    }
+    Ok(profile)
+}
+
+#[derive(Debug)]
+pub enum ColorError {
+    WrongLength(&'static str, usize),
+    ZeroGamma,
+    UnknownRenderingIntent(u8),
+    UnknownCompressionMethod(u8),
+    DecompressionFailed,
+    InvalidSignificantBits(u8, u8),
+    NotAllowed(&'static str, ColorType),
+    PaletteIndexTooLarge(u8, usize),
+    SampleTooLarge(u16, u8),
+}
+
+impl Display for ColorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WrongLength(chunk_type, length) => write!(f, "{} can't be {} bytes long", chunk_type, length),
+            ZeroGamma => write!(f, "The gamma must not be zero"),
+            UnknownRenderingIntent(intent) => write!(f, "Unknown rendering intent: {}", intent),
+            UnknownCompressionMethod(method) => write!(f, "Unknown compression method: {}", method),
+            DecompressionFailed => write!(f, "The ICC profile couldn't be decompressed"),
+            InvalidSignificantBits(bits, depth) => write!(f, "{} significant bits don't fit in samples of {} bits", bits, depth),
+            NotAllowed(chunk_type, color_type) => write!(f, "This {} doesn't fit {} images", chunk_type, color_type),
+            PaletteIndexTooLarge(index, entries) => write!(f, "The index {} is out of a palette of {} entries", index, entries),
+            SampleTooLarge(sample, bit_depth) => write!(f, "The sample {} doesn't fit in {} bits", sample, bit_depth),
+        }
+    }
+}
+
+impl ColorError {
+    /// A stable identifier for the error, for machine-readable output.
+    pub fn code(&self) -> &'static str {
+        match self {
+            WrongLength(_, _) => "wrong_length",
+            ZeroGamma => "zero_gamma",
+            UnknownRenderingIntent(_) => "unknown_rendering_intent",
+            UnknownCompressionMethod(_) => "unknown_compression_method",
+            DecompressionFailed => "decompression_failed",
+            InvalidSignificantBits(_, _) => "invalid_significant_bits",
+            NotAllowed(_, _) => "not_allowed",
+            PaletteIndexTooLarge(_, _) => "palette_index_too_large",
+            SampleTooLarge(_, _) => "sample_too_large",
+        }
+    }
+}
+
+impl std::error::Error for ColorError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ihdr::InterlaceMethod;
+    use crate::png::Png;
+    use crate::PngyinxError;
+
+    const PNG_FILE: &[u8] = include_bytes!("../pictures/dice.png");
+
+    fn ihdr(color_type: ColorType, bit_depth: u8) -> Ihdr {
+        Ihdr {
+            width: 4,
+            height: 4,
+            bit_depth,
+            color_type,
+            compression_method: 0,
+            filter_method: 0,
+            interlace_method: InterlaceMethod::None,
+        }
+    }
+
+    #[test]
+    fn test_dice_color_chunks() {
+        let png = Png::try_from(PNG_FILE).unwrap();
+        assert_eq!(png.rendering_intent().unwrap(), Some(RenderingIntent::Perceptual));
+        assert_eq!(png.gamma().unwrap(), Some(Gamma { gamma: 45455 }));
+        assert_eq!(png.chromaticities().unwrap(), None);
+        assert_eq!(png.icc_profile().unwrap(), None);
+    }
+
+    #[test]
+    fn test_fixed_lengths() {
+        assert!(matches!(Gamma::from_data(&[0, 0, 0, 0]), Err(PngyinxError::Color(ZeroGamma))));
+        assert!(matches!(Gamma::from_data(&[1]), Err(PngyinxError::Color(WrongLength("gAMA", 1)))));
+        assert!(matches!(RenderingIntent::from_data(&[4]), Err(PngyinxError::Color(UnknownRenderingIntent(4)))));
+        let chromaticities = Chromaticities {
+            white_x: 31270, white_y: 32900, red_x: 64000, red_y: 33000,
+            green_x: 30000, green_y: 60000, blue_x: 15000, blue_y: 6000,
+        };
+        let chunk = chromaticities.to_chunk().unwrap();
+        assert_eq!(chunk.length(), 32);
+        assert_eq!(Chromaticities::from_data(chunk.data()).unwrap(), chromaticities);
+        assert!(matches!(Chromaticities::from_data(&chunk.data()[1..]), Err(PngyinxError::Color(WrongLength("cHRM", 31)))));
+    }
+
+    #[test]
+    fn test_icc_profile_round_trip() {
+        let profile = IccProfile::new("Display P3", vec![7; 1000]).unwrap();
+        let chunk = profile.to_chunk().unwrap();
+        assert!(chunk.data().starts_with(b"Display P3\0\0"));
+        assert!(chunk.length() < 1000);
+        assert_eq!(IccProfile::from_data(chunk.data()).unwrap(), profile);
+        assert!(matches!(IccProfile::from_data(b"name\0\x01"), Err(PngyinxError::Color(UnknownCompressionMethod(1)))));
+        assert!(matches!(IccProfile::from_data(b"name\0\0garbage"), Err(PngyinxError::Color(DecompressionFailed))));
+        assert!(IccProfile::new(" name", Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_significant_bits() {
+        let rgba = ihdr(ColorType::TruecolorAlpha, 8);
+        assert!(SignificantBits::from_data(&[5, 6, 5, 8], &rgba).is_ok());
+        assert!(matches!(SignificantBits::from_data(&[5, 6, 5], &rgba), Err(PngyinxError::Color(WrongLength("sBIT", 3)))));
+        assert!(matches!(SignificantBits::from_data(&[5, 6, 5, 9], &rgba), Err(PngyinxError::Color(InvalidSignificantBits(9, 8)))));
+        assert!(SignificantBits::from_data(&[8, 8, 8], &ihdr(ColorType::Indexed, 2)).is_ok());
+        assert!(matches!(SignificantBits::from_data(&[3], &ihdr(ColorType::Grayscale, 2)), Err(PngyinxError::Color(InvalidSignificantBits(3, 2)))));
+    }
+
+    #[test]
+    fn test_background() {
+        let gray = ihdr(ColorType::GrayscaleAlpha, 16);
+        assert_eq!(Background::from_data(&[1, 0], &gray).unwrap(), Background::Grayscale(256));
+        assert!(matches!(Background::from_data(&[1, 0], &ihdr(ColorType::Grayscale, 4)), Err(PngyinxError::Color(SampleTooLarge(256, 4)))));
+        assert!(matches!(Background::from_data(&[0; 6], &gray), Err(PngyinxError::Color(WrongLength("bKGD", 6)))));
+        let palette = Palette::new(vec![[0, 0, 0]; 2]).unwrap();
+        let indexed = ihdr(ColorType::Indexed, 8);
+        assert!(matches!(Background::Indexed(2).check(&indexed, Some(&palette)), Err(PngyinxError::Color(PaletteIndexTooLarge(2, 2)))));
+        assert!(matches!(Background::Grayscale(0).check(&indexed, None), Err(PngyinxError::Color(NotAllowed("bKGD", _)))));
+    }
+
+    #[test]
+    fn test_png_setters() {
+        let mut png = Png::try_from(PNG_FILE).unwrap();
+        png.set_background(&Background::Truecolor(255, 255, 255)).unwrap();
+        png.set_significant_bits(&SignificantBits { bits: vec![8, 8, 8, 8] }).unwrap();
+        png.set_icc_profile(&IccProfile::new("sRGB", vec![1, 2, 3]).unwrap()).unwrap();
+        png.set_gamma(&Gamma { gamma: 100000 }).unwrap();
+        let types: Vec<String> = png.chunks().iter().map(|chunk| chunk.chunk_type().to_string()).collect();
+        assert_eq!(types, vec!["IHDR", "gAMA", "pHYs", "sBIT", "iCCP", "bKGD", "IDAT", "RuSt", "IEND"]);
+        assert_eq!(png.gamma().unwrap().unwrap().value(), 1.0);
+        assert_eq!(png.background().unwrap(), Some(Background::Truecolor(255, 255, 255)));
+        assert_eq!(png.icc_profile().unwrap().unwrap().profile, vec![1, 2, 3]);
+        assert!(png.set_background(&Background::Grayscale(0)).is_err());
+        assert!(png.validate().is_empty());
+
+        png.set_rendering_intent(RenderingIntent::Perceptual).unwrap();
+        assert_eq!(png.icc_profile().unwrap(), None);
+        assert_eq!(png.rendering_intent().unwrap(), Some(RenderingIntent::Perceptual));
+        assert!(png.validate().is_empty());
+    }
+}
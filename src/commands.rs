@@ -1,10 +1,46 @@
 use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use crate::chunk::Chunk;
-use crate::chunk_type::ChunkType;
-use crate::png::Png;
+use pngyinx::{crypto, inspect, payload, signature, split, Chunk, ChunkType, Exif, IccProfile, ImageTime, Png, PngReader, TextChunk};
+use pngyinx::color::SRGB_CHUNK_TYPE;
+use pngyinx::crypto::KdfParams;
+use pngyinx::exif::EXIF_CHUNK_TYPE;
+use pngyinx::payload::{FileMetadata, Payload, PayloadOptions};
+use pngyinx::strip::StripPolicy;
+use pngyinx::time::TIME_CHUNK_TYPE;
+use serde::Serialize;
+use serde_json::json;
+use crate::output::{cli_error, fail, print_json, Format};
 
-pub fn encode(path: &String, key: &String, message: &String) {
+pub struct EncodeOptions {
+    pub after_ihdr: bool,
+    pub file: Option<String>,
+    pub mime: Option<String>,
+    pub compression: Option<u32>,
+    pub passphrase: Option<String>,
+    pub recipient: Option<String>,
+    pub split: usize,
+    pub touch_time: bool,
+}
+
+pub struct DecodeOptions {
+    pub all: bool,
+    pub index: Option<usize>,
+    pub max_size: usize,
+    pub output: Option<String>,
+    pub passphrase: Option<String>,
+    pub identity: Option<String>,
+}
+
+pub struct TextOptions {
+    pub compress: bool,
+    pub language: Option<String>,
+    pub translated_keyword: Option<String>,
+}
+
+pub fn encode(path: &str, key: &str, message: Option<&str>, options: &EncodeOptions) {
     let data = fs::read(path).expect("Unable to read file");
     let png = Png::try_from(data.as_slice());
     if png.is_err() {
@@ -16,38 +52,658 @@ pub fn encode(path: &String, key: &String, message: &String) {
         panic!("La clé donnée est incorrecte")
     }
     let chunk_type = chunk_type.unwrap();
-    let chunk = Chunk::new(chunk_type, message.clone().into_bytes());
-    png.append_chunk(chunk);
-    fs::write(path, png.as_bytes()).expect("Unable to write file")
+    if chunk_type.is_critical() {
+        println!("Warning: {} is a critical chunk type, viewers may refuse to open the image", chunk_type);
+    }
+    let (message, file) = match &options.file {
+        Some(file_path) => {
+            let bytes = fs::read(file_path).expect("Unable to read the file to embed");
+            let name = Path::new(file_path).file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+            let metadata = FileMetadata { name, size: bytes.len() as u64, mime: options.mime.clone() };
+            (bytes, Some(metadata))
+        },
+        None => (message.unwrap_or_default().as_bytes().to_vec(), None),
+    };
+    let payload_options = PayloadOptions { compression: options.compression, file };
+    let mut payload = payload::encode_payload(&message, &payload_options).expect("Unable to build the payload");
+    if let Some(passphrase) = &options.passphrase {
+        payload = crypto::encrypt_with_passphrase(&payload, passphrase.as_bytes(), &KdfParams::default())
+            .expect("Unable to encrypt the message");
+    }
+    if let Some(recipient) = &options.recipient {
+        let recipient = read_key(recipient, Format::Text);
+        payload = crypto::encrypt_to_recipient(&payload, &recipient).expect("Unable to encrypt the message");
+    }
+    let fragments = split::split_payload(&payload, options.split).expect("Unable to split the message");
+    if fragments.len() > 1 {
+        println!("The message is split across {} chunks", fragments.len());
+    }
+    let chunks = fragments.into_iter().map(|fragment| Chunk::new(chunk_type, fragment));
+    if options.after_ihdr {
+        // Each insertion goes right after IHDR, so the last fragment goes in first.
+        for chunk in chunks.rev() {
+            png.insert_after("IHDR", chunk).expect("Le png donné n'a pas de IHDR");
+        }
+    } else {
+        chunks.for_each(|chunk| png.insert_before_iend(chunk));
+    }
+    touch(&mut png, options.touch_time);
+    write_png(path, &png)
+}
+
+pub fn decode(path: &str, key: &str, options: &DecodeOptions, format: Format) {
+    let chunks = match read_chunks(path, key) {
+        Ok(chunks) => chunks,
+        Err(e) => fail(format, e, "Le png donné est corrompu"),
+    };
+    let messages = split::join_all(chunks.iter().map(Chunk::data));
+    let selected: Vec<usize> = match options.index {
+        Some(index) if index >= messages.len() => {
+            let message = format!("Only {} messages were found", messages.len());
+            match format {
+                Format::Text => println!("{}", message),
+                Format::Json => print_json(&json!({
+                    "count": messages.len(),
+                    "messages": [],
+                    "error": cli_error("index_out_of_range", message),
+                })),
+            }
+            return;
+        },
+        Some(index) => vec![index],
+        None if options.all => (0..messages.len()).collect(),
+        None => (0..messages.len().min(1)).collect(),
+    };
+    // Files written to a directory are told apart by their index when several are decoded at once.
+    let name_index = |index| if selected.len() > 1 { Some(index) } else { None };
+    let count = messages.len();
+    let decoded: Vec<DecodedMessage> = messages.into_iter()
+        .enumerate()
+        .filter(|(index, _)| selected.contains(index))
+        .map(|(index, message)| decode_message(message, index, name_index(index), options, format))
+        .collect();
+    match format {
+        Format::Json => print_json(&json!({ "count": count, "messages": decoded })),
+        Format::Text if count == 0 => println!("Aucun message ne fut trouvé"),
+        Format::Text if options.all => {
+            println!("{} messages were found", count);
+            for message in &decoded {
+                println!("--- Message {} ---", message.index);
+                show_message(message);
+            }
+        },
+        Format::Text => decoded.iter().for_each(show_message),
+    }
+}
+
+fn read_chunks(path: &str, key: &str) -> pngyinx::Result<Vec<Chunk>> {
+    let reader = PngReader::new(BufReader::new(File::open(path)?))?;
+    let mut chunks = Vec::new();
+    for chunk in reader {
+        let chunk = chunk?;
+        if chunk.chunk_type().to_string() == key {
+            chunks.push(chunk);
+        }
+    }
+    Ok(chunks)
+}
+
+/// One message as `decode` reports it.
+#[derive(Serialize)]
+struct DecodedMessage {
+    index: usize,
+    /// The encryption scheme when the message is encrypted and no key was given to open it.
+    encryption: Option<&'static str>,
+    text: Option<String>,
+    size: Option<usize>,
+    file: Option<FileMetadata>,
+    output: Option<PathBuf>,
+    error: Option<pngyinx::Error>,
+}
+
+fn decode_message(message: pngyinx::Result<Vec<u8>>, index: usize, name_index: Option<usize>, options: &DecodeOptions, format: Format) -> DecodedMessage {
+    let mut decoded = DecodedMessage { index, encryption: None, text: None, size: None, file: None, output: None, error: None };
+    let data = match message {
+        Ok(data) => data,
+        Err(e) => {
+            decoded.error = Some(e);
+            return decoded;
+        },
+    };
+    if options.passphrase.is_none() && options.identity.is_none() && crypto::is_encrypted(&data) {
+        decoded.encryption = Some(match crypto::scheme(&data) {
+            Some(crypto::SCHEME_RECIPIENT) => "recipient",
+            _ => "passphrase",
+        });
+        return decoded;
+    }
+    let payload = match open_payload(&data, options, format) {
+        Ok(payload) => payload,
+        Err(e) => {
+            decoded.error = Some(e);
+            return decoded;
+        },
+    };
+    if let Some(output) = &options.output {
+        let output = output_path(output, payload.file.as_ref(), name_index);
+        if let Err(e) = fs::write(&output, &payload.data) {
+            fail(format, e.into(), "Unable to write the output file")
+        }
+        decoded.output = Some(output);
+    }
+    decoded.size = Some(payload.data.len());
+    if payload.file.is_none() {
+        decoded.text = String::from_utf8(payload.data).ok();
+    }
+    decoded.file = payload.file;
+    decoded
+}
+
+fn show_message(message: &DecodedMessage) {
+    if let Some(e) = &message.error {
+        println!("{}", e);
+        return;
+    }
+    match message.encryption {
+        Some("recipient") => return println!("The message is encrypted, use --identity to read it"),
+        Some(_) => return println!("The message is encrypted, use --passphrase or --passphrase-file to read it"),
+        None => {},
+    }
+    if let Some(file) = &message.file {
+        println!(
+            "File: {} ({} bytes{})",
+            file.name,
+            file.size,
+            file.mime.as_ref().map(|mime| format!(", {}", mime)).unwrap_or_default(),
+        );
+    }
+    if let Some(output) = &message.output {
+        println!("The message has been written to {}", output.display());
+        return;
+    }
+    match (&message.file, &message.text) {
+        (Some(_), _) => println!("Use --output to save the file"),
+        (None, Some(text)) => println!("Message: {}", text),
+        (None, None) => println!("The message is binary ({} bytes), use --output to save it", message.size.unwrap_or_default()),
+    }
+}
+
+/// Where to write a decoded message: `output` itself, or inside it when it is a directory,
+/// under the embedded file name stripped of any directory so that it can't escape `output`.
+/// When several messages are decoded at once, `index` keeps them apart: it is added to the name
+/// of `output` if it isn't a directory, and names the messages that aren't files inside it.
+fn output_path(output: &str, file: Option<&FileMetadata>, index: Option<usize>) -> PathBuf {
+    let output = Path::new(output);
+    if !output.is_dir() {
+        let Some(index) = index else {
+            return output.to_path_buf();
+        };
+        let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+        let name = match output.extension() {
+            Some(extension) => format!("{}-{}.{}", stem, index, extension.to_string_lossy()),
+            None => format!("{}-{}", stem, index),
+        };
+        return output.with_file_name(name);
+    }
+    let name = file
+        .and_then(|file| Path::new(&file.name).file_name().map(|name| name.to_os_string()))
+        .unwrap_or_else(|| match index {
+            Some(index) => format!("message-{}.bin", index).into(),
+            None => "message.bin".into(),
+        });
+    output.join(name)
+}
+
+fn open_payload(data: &[u8], options: &DecodeOptions, format: Format) -> pngyinx::Result<Payload> {
+    let data = if let Some(identity) = &options.identity {
+        crypto::decrypt_with_identity(data, &read_key(identity, format))?
+    } else if let Some(passphrase) = &options.passphrase {
+        crypto::decrypt_with_passphrase(data, passphrase.as_bytes())?
+    } else {
+        data.to_vec()
+    };
+    payload::decode_payload(&data, options.max_size)
+}
+
+/// Reads a hex-encoded key given either directly or as the path of a key file.
+fn read_key(key: &str, format: Format) -> [u8; 32] {
+    let text = if Path::new(key).is_file() {
+        match fs::read_to_string(key) {
+            Ok(text) => text,
+            Err(e) => fail(format, e.into(), "Unable to read the key file"),
+        }
+    } else {
+        key.to_string()
+    };
+    match crypto::key_from_hex(&text) {
+        Ok(key) => key,
+        Err(e) => fail(format, e, "The key must be 64 hexadecimal characters or the path of a key file"),
+    }
 }
 
-pub fn decode(path: &String, key: &String) {
+pub fn remove(path: &str, key: &str, all: bool, touch_time: bool) {
     let data = fs::read(path).expect("Unable to read file");
     let png = Png::try_from(data.as_slice());
     if png.is_err() {
         panic!("Le png donné est corrompu")
     }
-    let png = png.unwrap();
-    let result = png.chunk_by_type(key);
+    let mut png = png.unwrap();
+    if all {
+        match png.remove_chunks_by_type(key) {
+            Ok(chunks) => {
+                touch(&mut png, touch_time);
+                write_png(path, &png);
+                println!("{} chunks have been successfully deleted!", chunks.len())
+            },
+            Err(e) => println!("{}", e)
+        }
+        return;
+    }
+    let result = png.remove_chunk(key);
+    match result {
+        Ok(chunk) => {
+            if let Ok(removed) = split::parse_fragment(chunk.data()) {
+                let chunks = png.chunks().iter()
+                    .filter(|other| {
+                        other.chunk_type() != chunk.chunk_type()
+                            || split::parse_fragment(other.data()).map_or(true, |fragment| fragment.id != removed.id)
+                    })
+                    .cloned()
+                    .collect();
+                png = Png::from_chunks(chunks);
+            }
+            touch(&mut png, touch_time);
+            write_png(path, &png);
+            println!("The secret message has been successfully deleted!")
+        },
+        Err(e) => println!("{}", e)
+    }
+}
+
+pub fn validate(path: &str, format: Format) {
+    let violations = read_png(path, format).validate();
+    if format == Format::Json {
+        return print_json(&json!({ "valid": violations.is_empty(), "violations": violations }));
+    }
+    if violations.is_empty() {
+        println!("The png follows the specification!")
+    }
+    for violation in violations {
+        println!("{}", violation)
+    }
+}
+
+pub fn text_list(path: &str, format: Format) {
+    let (texts, errors): (Vec<_>, Vec<_>) = read_png(path, format).texts().into_iter().partition(Result::is_ok);
+    let texts: Vec<TextChunk> = texts.into_iter().map(Result::unwrap).collect();
+    let errors: Vec<pngyinx::Error> = errors.into_iter().map(Result::unwrap_err).collect();
+    if format == Format::Json {
+        return print_json(&json!({ "texts": texts, "errors": errors }));
+    }
+    if texts.is_empty() && errors.is_empty() {
+        println!("The png has no textual metadata");
+    }
+    for text in texts {
+        println!("{} ({})", text, text.chunk_type());
+    }
+    for e in errors {
+        println!("Warning: a malformed entry was skipped: {}", e);
+    }
+}
+
+pub fn text_get(path: &str, keyword: &str, format: Format) {
+    let texts: Vec<TextChunk> = read_png(path, format).texts()
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|text| text.keyword() == keyword)
+        .collect();
+    match format {
+        Format::Json if texts.is_empty() => print_json(&json!({
+            "texts": texts,
+            "error": cli_error("keyword_not_found", format!("There is no entry with the keyword {}", keyword)),
+        })),
+        Format::Json => print_json(&json!({ "texts": texts, "error": null })),
+        Format::Text if texts.is_empty() => println!("There is no entry with the keyword {}", keyword),
+        Format::Text => texts.iter().for_each(|text| println!("{}", text.text())),
+    }
+}
+
+pub fn text_set(path: &str, keyword: &str, text: &str, options: &TextOptions, touch_time: bool) {
+    let mut png = read_png(path, Format::Text);
+    let text = if options.language.is_some() || options.translated_keyword.is_some() {
+        TextChunk::International {
+            keyword: keyword.to_string(),
+            compressed: options.compress,
+            language: options.language.clone().unwrap_or_default(),
+            translated_keyword: options.translated_keyword.clone().unwrap_or_default(),
+            text: text.to_string(),
+        }
+    } else if options.compress {
+        TextChunk::compressed(keyword, text)
+    } else {
+        TextChunk::new(keyword, text)
+    };
+    match png.set_text(&text) {
+        Ok(()) => {
+            touch(&mut png, touch_time);
+            write_png(path, &png);
+            println!("The entry {} has been saved as {}!", keyword, text.chunk_type())
+        },
+        Err(e) => println!("{}", e),
+    }
+}
+
+pub fn text_delete(path: &str, keyword: &str, touch_time: bool) {
+    let mut png = read_png(path, Format::Text);
+    match png.remove_texts(keyword) {
+        Ok(texts) => {
+            touch(&mut png, touch_time);
+            write_png(path, &png);
+            println!("{} entries have been deleted!", texts.len())
+        },
+        Err(_) => println!("There is no entry with the keyword {}", keyword),
+    }
+}
+
+pub fn icc_extract(path: &str, output: &str) {
+    match read_png(path, Format::Text).icc_profile() {
+        Ok(Some(profile)) => {
+            fs::write(output, &profile.profile).expect("Unable to write the profile");
+            println!("The profile {} ({} bytes) was written to {}", profile.name, profile.profile.len(), output)
+        },
+        Ok(None) => println!("There is no ICC profile in {}", path),
+        Err(e) => println!("{}", e),
+    }
+}
+
+pub fn icc_embed(path: &str, profile: &str, name: &str, touch_time: bool) {
+    let mut png = read_png(path, Format::Text);
+    let profile = fs::read(profile).expect("Unable to read the profile");
+    let replaced = png.chunk_by_type(SRGB_CHUNK_TYPE).is_some();
+    let result = IccProfile::new(name, profile).and_then(|profile| png.set_icc_profile(&profile));
     match result {
-        Some(e) => println!("Message: {}", e.data_as_string().unwrap()),
-        None => println!("Aucun message ne fut trouvé")
+        Ok(()) => {
+            touch(&mut png, touch_time);
+            write_png(path, &png);
+            println!("The profile {} has been embedded!", name);
+            if replaced {
+                println!("It replaces the sRGB chunk");
+            }
+        },
+        Err(e) => println!("{}", e),
+    }
+}
+
+pub fn exif_list(path: &str, format: Format) {
+    let exif = match read_png(path, format).exif() {
+        Ok(exif) => exif,
+        Err(e) => fail(format, e, "Les données EXIF sont corrompues"),
+    };
+    if format == Format::Json {
+        let entries: Vec<_> = exif.iter()
+            .flat_map(|exif| exif.entries())
+            .map(|entry| json!({
+                "ifd": entry.ifd,
+                "tag": entry.tag,
+                "name": entry.name(),
+                "value": entry.value.to_string(),
+            }))
+            .collect();
+        let unsupported: Vec<String> = exif.iter().flat_map(|exif| exif.unsupported()).map(ToString::to_string).collect();
+        return print_json(&json!({
+            "byte_order": exif.as_ref().map(Exif::byte_order),
+            "entries": entries,
+            "unsupported": unsupported,
+        }));
+    }
+    let Some(exif) = exif else {
+        return println!("There is no EXIF metadata in {}", path);
+    };
+    for entry in exif.entries() {
+        println!("{:<7}  {:<18}  {:#06x}  {}", entry.ifd.to_string(), entry.name().unwrap_or("Unknown"), entry.tag, entry.value);
+    }
+    for unsupported in exif.unsupported() {
+        println!("Kept as is but not rewritable: {}", unsupported);
+    }
+    if let Some((latitude, longitude)) = exif.gps_coordinates() {
+        println!("Location: {:.6}, {:.6}", latitude, longitude);
+    }
+}
+
+pub fn exif_remove(path: &str, gps: bool, touch_time: bool) {
+    let mut png = read_png(path, Format::Text);
+    if !gps {
+        return match png.remove_chunks_by_type(EXIF_CHUNK_TYPE) {
+            Ok(_) => {
+                touch(&mut png, touch_time);
+                write_png(path, &png);
+                println!("The EXIF metadata has been deleted!")
+            },
+            Err(_) => println!("There is no EXIF metadata in {}", path),
+        };
+    }
+    if png.chunk_by_type(EXIF_CHUNK_TYPE).is_none() {
+        return println!("There is no EXIF metadata in {}", path);
+    }
+    match png.erase_gps() {
+        Ok(0) => println!("There is no GPS tag in {}", path),
+        Ok(removed) => {
+            touch(&mut png, touch_time);
+            write_png(path, &png);
+            println!("{} GPS tags have been deleted!", removed)
+        },
+        Err(e) => println!("{}", e),
+    }
+}
+
+pub fn strip(path: &str, policy: &StripPolicy, format: Format, touch_time: bool) {
+    let mut png = read_png(path, format);
+    let removed = match png.strip(policy) {
+        Ok(removed) => removed,
+        Err(e) if format == Format::Json => fail(format, e, ""),
+        Err(e) => return println!("{}", e),
+    };
+    let mut by_type: Vec<(ChunkType, usize, usize)> = Vec::new();
+    for chunk in &removed {
+        let bytes = chunk.as_bytes().len();
+        match by_type.iter_mut().find(|(chunk_type, _, _)| chunk_type == chunk.chunk_type()) {
+            Some((_, count, total)) => {
+                *count += 1;
+                *total += bytes;
+            },
+            None => by_type.push((*chunk.chunk_type(), 1, bytes)),
+        }
     }
+    let saved: usize = by_type.iter().map(|(_, _, bytes)| bytes).sum();
+    if !removed.is_empty() {
+        // A new tIME would bring back what the policy strips.
+        if !policy.strips(&ChunkType::from_str(TIME_CHUNK_TYPE).unwrap()) {
+            touch(&mut png, touch_time);
+        }
+        write_png(path, &png);
+    }
+    if format == Format::Json {
+        let removed: Vec<_> = by_type.iter()
+            .map(|(chunk_type, count, bytes)| json!({ "chunk_type": chunk_type, "count": count, "bytes": bytes }))
+            .collect();
+        return print_json(&json!({
+            "removed": removed,
+            "bytes_saved": saved,
+            "size": png.as_bytes().len(),
+        }));
+    }
+    if removed.is_empty() {
+        return println!("There is nothing to strip");
+    }
+    for (chunk_type, count, bytes) in &by_type {
+        println!("{}  {:>4} chunks  {:>10} bytes", chunk_type, count, bytes);
+    }
+    println!("{} chunks removed, {} bytes saved", removed.len(), saved);
+}
+
+pub fn inspect(path: &str, format: Format) {
+    let data = fs::read(path).map_err(pngyinx::Error::from).and_then(|data| inspect::inspect(&data));
+    let report = match data {
+        Ok(report) => report,
+        Err(e) => fail(format, e, "Le png donné est corrompu"),
+    };
+    if format == Format::Json {
+        let chunks: Vec<_> = report.chunks.iter()
+            .map(|chunk| json!({
+                "index": chunk.index,
+                "offset": chunk.offset,
+                "chunk_type": chunk.chunk_type,
+                "length": chunk.length,
+                "stored_crc": chunk.stored_crc,
+                "computed_crc": chunk.computed_crc,
+                "crc_matches": chunk.crc_matches(),
+                "critical": chunk.chunk_type.is_critical(),
+                "public": chunk.chunk_type.is_public(),
+                "reserved_bit_valid": chunk.chunk_type.is_reserved_bit_valid(),
+                "safe_to_copy": chunk.chunk_type.is_safe_to_copy(),
+                "unknown": chunk.is_unknown(),
+            }))
+            .collect();
+        return print_json(&json!({
+            "size": report.size,
+            "chunks": chunks,
+            "unknown_types": report.unknown_types(),
+            "ihdr": match &report.ihdr {
+                Some(Ok(ihdr)) => json!(ihdr),
+                Some(Err(e)) => json!({ "error": e }),
+                None => json!(null),
+            },
+            "time": match &report.time {
+                Some(Ok(time)) => json!(time.to_string()),
+                Some(Err(e)) => json!({ "error": e }),
+                None => json!(null),
+            },
+            "error": report.error,
+        }));
+    }
+    println!(
+        "{:>5}  {:>10}  {:<4}  {:>10}  {:<8}  {:<8}  {:<8}  {:<6}  {:<11}  Safe to copy",
+        "Index", "Offset", "Type", "Length", "CRC", "Computed", "Critical", "Public", "Reserved OK",
+    );
+    for chunk in &report.chunks {
+        let chunk_type = chunk.chunk_type;
+        println!(
+            "{:>5}  {:>10}  {:<4}  {:>10}  {:08x}  {:08x}  {:<8}  {:<6}  {:<11}  {}{}",
+            chunk.index,
+            chunk.offset,
+            chunk_type,
+            chunk.length,
+            chunk.stored_crc,
+            chunk.computed_crc,
+            yes_no(chunk_type.is_critical()),
+            yes_no(chunk_type.is_public()),
+            yes_no(chunk_type.is_reserved_bit_valid()),
+            yes_no(chunk_type.is_safe_to_copy()),
+            if chunk.crc_matches() { "" } else { "  CRC mismatch!" },
+        );
+    }
+    println!("{} chunks, {} bytes in total", report.chunks.len(), report.size);
+    match &report.ihdr {
+        Some(Ok(ihdr)) => println!("Image: {}", ihdr),
+        Some(Err(e)) => println!("Image: {}", e),
+        None => println!("Image: no IHDR chunk"),
+    }
+    match &report.time {
+        Some(Ok(time)) => println!("Last modified: {}", time),
+        Some(Err(e)) => println!("Last modified: {}", e),
+        None => {},
+    }
+    let unknown_types: Vec<String> = report.unknown_types().iter().map(ChunkType::to_string).collect();
+    if unknown_types.is_empty() {
+        println!("No unknown chunk type");
+    } else {
+        println!("Unknown chunk types: {}", unknown_types.join(", "));
+    }
+    if let Some(e) = report.error {
+        println!("The chunks stop early: {}", e);
+    }
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value { "yes" } else { "no" }
+}
+
+pub fn keygen(path: &str, signing: bool) {
+    let (identity, recipient) = if signing {
+        signature::generate_signing_keypair()
+    } else {
+        crypto::generate_keypair()
+    };
+    let public_path = format!("{}.pub", path);
+    // Existing keys are never overwritten, checked up front so that no half of a pair is written.
+    for path in [path, public_path.as_str()] {
+        if Path::new(path).exists() {
+            panic!("{} already exists, remove it or choose another path", path)
+        }
+    }
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path).expect("Unable to write file");
+    writeln!(file, "{}", crypto::key_to_hex(&identity)).expect("Unable to write file");
+    let mut file = OpenOptions::new().write(true).create_new(true).open(&public_path).expect("Unable to write file");
+    writeln!(file, "{}", crypto::key_to_hex(&recipient)).expect("Unable to write file");
+    println!("Your identity was written to {}, keep it secret!", path);
+    println!("Your public key was written to {}: {}", public_path, crypto::key_to_hex(&recipient));
 }
 
-pub fn remove(path: &String, key: &String) {
+pub fn sign(path: &str, key: &str, touch_time: bool) {
     let data = fs::read(path).expect("Unable to read file");
     let png = Png::try_from(data.as_slice());
     if png.is_err() {
         panic!("Le png donné est corrompu")
     }
     let mut png = png.unwrap();
-    let result = png.remove_chunk(key);
+    // Touched first so that the signature covers the new time.
+    touch(&mut png, touch_time);
+    signature::sign(&mut png, &read_key(key, Format::Text)).expect("Unable to sign the png");
+    write_png(path, &png);
+    println!("The png has been signed!")
+}
+
+pub fn verify(path: &str, public_key: &str, format: Format) {
+    let result = signature::verify(&read_png(path, format), &read_key(public_key, format));
+    if format == Format::Json {
+        return match result {
+            Ok(changes) => print_json(&json!({ "valid": true, "changes": changes, "error": null })),
+            Err(e) => print_json(&json!({ "valid": false, "changes": [], "error": e })),
+        };
+    }
     match result {
-        Ok(_chunk) => {
-            fs::write(path, png.as_bytes()).expect("Unable to write file");
-            println!("The secret message has been successfully deleted!")
+        Ok(changes) if changes.is_empty() => println!("The signature is valid and nothing changed since signing!"),
+        Ok(changes) => {
+            println!("The signature is valid but the png changed since signing:");
+            for change in changes {
+                println!("{}", change)
+            }
         },
-        Err(e) => println!("{}", e)
+        Err(e) => println!("{}", e),
+    }
+}
+
+/// Reads the png at `path` for a read-only command.
+fn read_png(path: &str, format: Format) -> Png {
+    let png = fs::read(path).map_err(pngyinx::Error::from).and_then(|data| Png::try_from(data.as_slice()));
+    match png {
+        Ok(png) => png,
+        Err(e) => fail(format, e, "Le png donné est corrompu"),
+    }
+}
+
+/// Records the current time in tIME when `touch_time` is set, before a changed png is written.
+fn touch(png: &mut Png, touch_time: bool) {
+    if touch_time {
+        png.set_time(&ImageTime::now()).expect("Unable to update tIME");
     }
-}
\ No newline at end of file
+}
+
+fn write_png(path: &str, png: &Png) {
+    let file = File::create(path).expect("Unable to write file");
+    png.write_to(BufWriter::new(file)).expect("Unable to write file");
+}
@@ -0,0 +1,318 @@
+use std::fmt::{Display, Formatter};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use hkdf::Hkdf;
+use rand_core::{OsRng, RngCore};
+use sha2::Sha256;
+use x25519_dalek::{PublicKey, StaticSecret};
+use crate::crypto::CryptoError::{AuthenticationFailed, InvalidKdfParams, InvalidKey, NotEncrypted, TruncatedHeader, UnsupportedScheme, UnsupportedVersion};
+use crate::Result;
+
+/// Every encrypted payload starts with these bytes, followed by the format version and the scheme.
+pub const MAGIC: [u8; 3] = *b"PYX";
+pub const VERSION: u8 = 1;
+pub const SCHEME_PASSPHRASE: u8 = 1;
+pub const SCHEME_RECIPIENT: u8 = 2;
+
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 24;
+const KEY_LENGTH: usize = 32;
+const PASSPHRASE_HEADER_LENGTH: usize = 5 + 12 + SALT_LENGTH + NONCE_LENGTH;
+const RECIPIENT_HEADER_LENGTH: usize = 5 + KEY_LENGTH + NONCE_LENGTH;
+const RECIPIENT_KDF_INFO: &[u8] = b"pngyinx x25519 v1";
+
+/// Upper bounds on the Argon2 costs read from a file, so that a crafted header can't make us
+/// allocate more than 256 MiB (the memory cost is in KiB) or hash for minutes.
+const MAX_M_COST: u32 = 1 << 18;
+const MAX_T_COST: u32 = 8;
+const MAX_P_COST: u32 = 4;
+
+/// Argon2id parameters, stored in the payload header so that decryption doesn't need them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KdfParams {
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        KdfParams {
+            m_cost: Params::DEFAULT_M_COST,
+            t_cost: Params::DEFAULT_T_COST,
+            p_cost: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+/// Whether `data` looks like a payload produced by this module. Plaintext that happens to start
+/// with `MAGIC` is given a payload header by `encode_payload`, so it can't be mistaken for one.
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(&MAGIC)
+}
+
+/// The scheme byte of an encrypted payload, `None` if `data` isn't encrypted.
+pub fn scheme(data: &[u8]) -> Option<u8> {
+    if is_encrypted(data) {
+        data.get(4).copied()
+    } else {
+        None
+    }
+}
+
+/// Generates an X25519 keypair, returned as `(secret, public)`.
+pub fn generate_keypair() -> ([u8; KEY_LENGTH], [u8; KEY_LENGTH]) {
+    let secret = StaticSecret::random_from_rng(OsRng);
+    let public = PublicKey::from(&secret);
+    (secret.to_bytes(), public.to_bytes())
+}
+
+pub fn public_key(identity: &[u8; KEY_LENGTH]) -> [u8; KEY_LENGTH] {
+    PublicKey::from(&StaticSecret::from(*identity)).to_bytes()
+}
+
+pub fn key_to_hex(key: &[u8; KEY_LENGTH]) -> String {
+    hex::encode(key)
+}
+
+/// Parses a hex-encoded key, ignoring surrounding whitespace.
+pub fn key_from_hex(text: &str) -> Result<[u8; KEY_LENGTH]> {
+    let mut key = [0u8; KEY_LENGTH];
+    hex::decode_to_slice(text.trim(), &mut key).map_err(|_| InvalidKey)?;
+    Ok(key)
+}
+
+/// Encrypts `plaintext` with XChaCha20-Poly1305 under a key derived from `passphrase` with Argon2id.
+///
+/// The header (magic, version, scheme, KDF parameters, salt and nonce) is authenticated along with the data.
+pub fn encrypt_with_passphrase(plaintext: &[u8], passphrase: &[u8], params: &KdfParams) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LENGTH];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let key = derive_key(passphrase, &salt, params)?;
+
+    let mut header = Vec::with_capacity(PASSPHRASE_HEADER_LENGTH);
+    header.extend_from_slice(&MAGIC);
+    header.push(VERSION);
+    header.push(SCHEME_PASSPHRASE);
+    header.extend_from_slice(&params.m_cost.to_be_bytes());
+    header.extend_from_slice(&params.t_cost.to_be_bytes());
+    header.extend_from_slice(&params.p_cost.to_be_bytes());
+    header.extend_from_slice(&salt);
+    header.extend_from_slice(&nonce);
+
+    let ciphertext = XChaCha20Poly1305::new(&key)
+        .encrypt(&nonce, Payload { msg: plaintext, aad: &header })
+        .map_err(|_| AuthenticationFailed)?;
+    header.extend(ciphertext);
+    Ok(header)
+}
+
+pub fn decrypt_with_passphrase(data: &[u8], passphrase: &[u8]) -> Result<Vec<u8>> {
+    check_header(data, SCHEME_PASSPHRASE)?;
+    let (header, ciphertext) = data.split_at_checked(PASSPHRASE_HEADER_LENGTH).ok_or(TruncatedHeader)?;
+    let params = KdfParams {
+        m_cost: u32::from_be_bytes(header[5..9].try_into().unwrap()),
+        t_cost: u32::from_be_bytes(header[9..13].try_into().unwrap()),
+        p_cost: u32::from_be_bytes(header[13..17].try_into().unwrap()),
+    };
+    if params.m_cost > MAX_M_COST || params.t_cost > MAX_T_COST || params.p_cost > MAX_P_COST {
+        return Err(InvalidKdfParams.into());
+    }
+    let salt = &header[17..17 + SALT_LENGTH];
+    let nonce = XNonce::from_slice(&header[17 + SALT_LENGTH..]);
+    let key = derive_key(passphrase, salt, &params)?;
+
+    XChaCha20Poly1305::new(&key)
+        .decrypt(nonce, Payload { msg: ciphertext, aad: header })
+        .map_err(|_| AuthenticationFailed.into())
+}
+
+/// Seals `plaintext` to the owner of the X25519 public key `recipient`.
+///
+/// An ephemeral keypair is generated for every message; the shared secret goes through HKDF-SHA256
+/// to key XChaCha20-Poly1305, and the ephemeral public key is stored in the header.
+pub fn encrypt_to_recipient(plaintext: &[u8], recipient: &[u8; KEY_LENGTH]) -> Result<Vec<u8>> {
+    let recipient = PublicKey::from(*recipient);
+    let ephemeral = StaticSecret::random_from_rng(OsRng);
+    let ephemeral_public = PublicKey::from(&ephemeral);
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let key = recipient_key(&ephemeral.diffie_hellman(&recipient).to_bytes(), &ephemeral_public, &recipient);
+
+    let mut header = Vec::with_capacity(RECIPIENT_HEADER_LENGTH);
+    header.extend_from_slice(&MAGIC);
+    header.push(VERSION);
+    header.push(SCHEME_RECIPIENT);
+    header.extend_from_slice(ephemeral_public.as_bytes());
+    header.extend_from_slice(&nonce);
+
+    let ciphertext = XChaCha20Poly1305::new(&key)
+        .encrypt(&nonce, Payload { msg: plaintext, aad: &header })
+        .map_err(|_| AuthenticationFailed)?;
+    header.extend(ciphertext);
+    Ok(header)
+}
+
+pub fn decrypt_with_identity(data: &[u8], identity: &[u8; KEY_LENGTH]) -> Result<Vec<u8>> {
+    check_header(data, SCHEME_RECIPIENT)?;
+    let (header, ciphertext) = data.split_at_checked(RECIPIENT_HEADER_LENGTH).ok_or(TruncatedHeader)?;
+    let ephemeral_public: [u8; KEY_LENGTH] = header[5..5 + KEY_LENGTH].try_into().unwrap();
+    let ephemeral_public = PublicKey::from(ephemeral_public);
+    let nonce = XNonce::from_slice(&header[5 + KEY_LENGTH..]);
+    let identity = StaticSecret::from(*identity);
+    let key = recipient_key(&identity.diffie_hellman(&ephemeral_public).to_bytes(), &ephemeral_public, &PublicKey::from(&identity));
+
+    XChaCha20Poly1305::new(&key)
+        .decrypt(nonce, Payload { msg: ciphertext, aad: header })
+        .map_err(|_| AuthenticationFailed.into())
+}
+
+fn recipient_key(shared_secret: &[u8], ephemeral_public: &PublicKey, recipient: &PublicKey) -> Key {
+    let salt = [ephemeral_public.as_bytes().as_slice(), recipient.as_bytes()].concat();
+    let mut key = Key::default();
+    Hkdf::<Sha256>::new(Some(&salt), shared_secret)
+        .expand(RECIPIENT_KDF_INFO, &mut key)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    key
+}
+
+fn check_header(data: &[u8], scheme: u8) -> Result<()> {
+    if !is_encrypted(data) {
+        return Err(NotEncrypted.into());
+    }
+    match (data.get(3), data.get(4)) {
+        (Some(&VERSION), Some(&found)) if found == scheme => Ok(()),
+        (Some(&VERSION), Some(&found)) => Err(UnsupportedScheme(found).into()),
+        (Some(&version), Some(_)) => Err(UnsupportedVersion(version).into()),
+        _ => Err(TruncatedHeader.into()),
+    }
+}
+
+fn derive_key(passphrase: &[u8], salt: &[u8], params: &KdfParams) -> Result<Key> {
+    let params = Params::new(params.m_cost, params.t_cost, params.p_cost, Some(32))
+        .map_err(|_| InvalidKdfParams)?;
+    let mut key = Key::default();
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase, salt, &mut key)
+        .map_err(|_| InvalidKdfParams)?;
+    Ok(key)
+}
+
+#[derive(Debug)]
+pub enum CryptoError {
+    NotEncrypted,
+    UnsupportedVersion(u8),
+    UnsupportedScheme(u8),
+    TruncatedHeader,
+    InvalidKdfParams,
+    InvalidKey,
+    AuthenticationFailed,
+}
+
+impl Display for CryptoError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NotEncrypted => write!(f, "The message isn't encrypted"),
+            UnsupportedVersion(version) => write!(f, "Unsupported encryption format version: {}", version),
+            UnsupportedScheme(scheme) => write!(f, "Unsupported encryption scheme: {}", scheme),
+            TruncatedHeader => write!(f, "The encryption header is truncated"),
+            InvalidKdfParams => write!(f, "The key derivation parameters are invalid"),
+            InvalidKey => write!(f, "The key must be 32 bytes written in hexadecimal"),
+            AuthenticationFailed => write!(f, "Authentication failed: wrong passphrase or key, or tampered data"),
+        }
+    }
+}
+
+impl CryptoError {
+    /// A stable identifier for the error, for machine-readable output.
+    pub fn code(&self) -> &'static str {
+        match self {
+            NotEncrypted => "not_encrypted",
+            UnsupportedVersion(_) => "unsupported_version",
+            UnsupportedScheme(_) => "unsupported_scheme",
+            TruncatedHeader => "truncated_header",
+            InvalidKdfParams => "invalid_kdf_params",
+            InvalidKey => "invalid_key",
+            AuthenticationFailed => "authentication_failed",
+        }
+    }
+}
+
+impl std::error::Error for CryptoError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PngyinxError;
+
+    const TEST_PARAMS: KdfParams = KdfParams { m_cost: 64, t_cost: 1, p_cost: 1 };
+
+    #[test]
+    fn test_passphrase_round_trip() {
+        let data = encrypt_with_passphrase(b"This is a secret", b"hunter2", &TEST_PARAMS).unwrap();
+        assert!(is_encrypted(&data));
+        assert!(!data.windows(16).any(|window| window == b"This is a secret"));
+        let plaintext = decrypt_with_passphrase(&data, b"hunter2").unwrap();
+        assert_eq!(plaintext, b"This is a secret");
+    }
+
+    #[test]
+    fn test_wrong_passphrase() {
+        let data = encrypt_with_passphrase(b"This is a secret", b"hunter2", &TEST_PARAMS).unwrap();
+        let plaintext = decrypt_with_passphrase(&data, b"hunter3");
+        assert!(matches!(plaintext, Err(PngyinxError::Crypto(AuthenticationFailed))));
+    }
+
+    #[test]
+    fn test_tampered_data() {
+        let mut data = encrypt_with_passphrase(b"This is a secret", b"hunter2", &TEST_PARAMS).unwrap();
+        let last = data.len() - 1;
+        data[last] ^= 1;
+        assert!(matches!(decrypt_with_passphrase(&data, b"hunter2"), Err(PngyinxError::Crypto(AuthenticationFailed))));
+
+        let mut data = encrypt_with_passphrase(b"This is a secret", b"hunter2", &TEST_PARAMS).unwrap();
+        data[20] ^= 1;
+        assert!(matches!(decrypt_with_passphrase(&data, b"hunter2"), Err(PngyinxError::Crypto(AuthenticationFailed))));
+    }
+
+    #[test]
+    fn test_recipient_round_trip() {
+        let (identity, recipient) = generate_keypair();
+        assert_eq!(public_key(&identity), recipient);
+        let data = encrypt_to_recipient(b"This is a secret", &recipient).unwrap();
+        assert_eq!(scheme(&data), Some(SCHEME_RECIPIENT));
+        let plaintext = decrypt_with_identity(&data, &identity).unwrap();
+        assert_eq!(plaintext, b"This is a secret");
+    }
+
+    #[test]
+    fn test_wrong_identity() {
+        let (_identity, recipient) = generate_keypair();
+        let (other, _) = generate_keypair();
+        let data = encrypt_to_recipient(b"This is a secret", &recipient).unwrap();
+        assert!(matches!(decrypt_with_identity(&data, &other), Err(PngyinxError::Crypto(AuthenticationFailed))));
+
+        let data = encrypt_with_passphrase(b"This is a secret", b"hunter2", &TEST_PARAMS).unwrap();
+        assert!(matches!(decrypt_with_identity(&data, &other), Err(PngyinxError::Crypto(UnsupportedScheme(SCHEME_PASSPHRASE)))));
+    }
+
+    #[test]
+    fn test_key_hex() {
+        let (identity, _) = generate_keypair();
+        let text = format!("{}\n", key_to_hex(&identity));
+        assert_eq!(key_from_hex(&text).unwrap(), identity);
+        assert!(matches!(key_from_hex("abcd"), Err(PngyinxError::Crypto(InvalidKey))));
+    }
+
+    #[test]
+    fn test_invalid_headers() {
+        assert!(matches!(decrypt_with_passphrase(b"plain text", b"hunter2"), Err(PngyinxError::Crypto(NotEncrypted))));
+        assert!(matches!(decrypt_with_passphrase(b"PYX\x02\x01", b"hunter2"), Err(PngyinxError::Crypto(UnsupportedVersion(2)))));
+        assert!(matches!(decrypt_with_passphrase(b"PYX\x01\x01", b"hunter2"), Err(PngyinxError::Crypto(TruncatedHeader))));
+
+        let mut data = encrypt_with_passphrase(b"This is a secret", b"hunter2", &TEST_PARAMS).unwrap();
+        data[5..9].copy_from_slice(&(1u32 << 19).to_be_bytes());
+        assert!(matches!(decrypt_with_passphrase(&data, b"hunter2"), Err(PngyinxError::Crypto(InvalidKdfParams))));
+    }
+}
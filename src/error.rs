@@ -0,0 +1,286 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+use crate::chunk::ChunkError;
+use crate::chunk_type::ChunkTypeDecodingError;
+use crate::color::ColorError;
+use crate::crypto::CryptoError;
+use crate::exif::ExifError;
+use crate::ihdr::IhdrError;
+use crate::image::ImageError;
+use crate::palette::PaletteError;
+use crate::payload::PayloadError;
+use crate::physical::PhysicalError;
+use crate::png::PngError;
+use crate::signature::SignatureError;
+use crate::split::SplitError;
+use crate::strip::StripError;
+use crate::text::TextError;
+use crate::time::TimeError;
+
+#[derive(Debug)]
+pub enum PngyinxError {
+    Chunk(ChunkError),
+    ChunkType(ChunkTypeDecodingError),
+    Png(PngError),
+    Ihdr(IhdrError),
+    Palette(PaletteError),
+    Text(TextError),
+    Time(TimeError),
+    Color(ColorError),
+    Physical(PhysicalError),
+    Exif(ExifError),
+    Image(ImageError),
+    Crypto(CryptoError),
+    Signature(SignatureError),
+    Payload(PayloadError),
+    Split(SplitError),
+    Strip(StripError),
+    Io(io::Error),
+}
+
+impl Display for PngyinxError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PngyinxError::Chunk(e) => write!(f, "Invalid chunk: {}", e),
+            PngyinxError::ChunkType(e) => write!(f, "Invalid chunk type: {}", e),
+            PngyinxError::Png(e) => write!(f, "Invalid png: {}", e),
+            PngyinxError::Ihdr(e) => write!(f, "Invalid IHDR: {}", e),
+            PngyinxError::Palette(e) => write!(f, "Invalid palette: {}", e),
+            PngyinxError::Text(e) => write!(f, "Invalid text: {}", e),
+            PngyinxError::Time(e) => write!(f, "Invalid time: {}", e),
+            PngyinxError::Color(e) => write!(f, "Invalid color metadata: {}", e),
+            PngyinxError::Physical(e) => write!(f, "Invalid physical dimensions: {}", e),
+            PngyinxError::Exif(e) => write!(f, "Invalid EXIF: {}", e),
+            PngyinxError::Image(e) => write!(f, "Invalid image data: {}", e),
+            PngyinxError::Crypto(e) => write!(f, "Encryption error: {}", e),
+            PngyinxError::Signature(e) => write!(f, "Signature error: {}", e),
+            PngyinxError::Payload(e) => write!(f, "Invalid payload: {}", e),
+            PngyinxError::Split(e) => write!(f, "Invalid split payload: {}", e),
+            PngyinxError::Strip(e) => write!(f, "Invalid strip policy: {}", e),
+            PngyinxError::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+
+impl PngyinxError {
+    /// The module the error comes from, e.g. `"chunk"` or `"crypto"`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PngyinxError::Chunk(_) => "chunk",
+            PngyinxError::ChunkType(_) => "chunk_type",
+            PngyinxError::Png(_) => "png",
+            PngyinxError::Ihdr(_) => "ihdr",
+            PngyinxError::Palette(_) => "palette",
+            PngyinxError::Text(_) => "text",
+            PngyinxError::Time(_) => "time",
+            PngyinxError::Color(_) => "color",
+            PngyinxError::Physical(_) => "physical",
+            PngyinxError::Exif(_) => "exif",
+            PngyinxError::Image(_) => "image",
+            PngyinxError::Crypto(_) => "crypto",
+            PngyinxError::Signature(_) => "signature",
+            PngyinxError::Payload(_) => "payload",
+            PngyinxError::Split(_) => "split",
+            PngyinxError::Strip(_) => "strip",
+            PngyinxError::Io(_) => "io",
+        }
+    }
+
+    /// A stable identifier for the error within its `kind`, for machine-readable output.
+    pub fn code(&self) -> &'static str {
+        match self {
+            PngyinxError::Chunk(e) => e.code(),
+            PngyinxError::ChunkType(e) => e.code(),
+            PngyinxError::Png(e) => e.code(),
+            PngyinxError::Ihdr(e) => e.code(),
+            PngyinxError::Palette(e) => e.code(),
+            PngyinxError::Text(e) => e.code(),
+            PngyinxError::Time(e) => e.code(),
+            PngyinxError::Color(e) => e.code(),
+            PngyinxError::Physical(e) => e.code(),
+            PngyinxError::Exif(e) => e.code(),
+            PngyinxError::Image(e) => e.code(),
+            PngyinxError::Crypto(e) => e.code(),
+            PngyinxError::Signature(e) => e.code(),
+            PngyinxError::Payload(e) => e.code(),
+            PngyinxError::Split(e) => e.code(),
+            PngyinxError::Strip(e) => e.code(),
+            PngyinxError::Io(_) => "io",
+        }
+    }
+}
+
+/// Serialized as `{"kind", "code", "message"}`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for PngyinxError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("Error", 3)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+impl std::error::Error for PngyinxError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PngyinxError::Chunk(e) => Some(e),
+            PngyinxError::ChunkType(e) => Some(e),
+            PngyinxError::Png(e) => Some(e),
+            PngyinxError::Ihdr(e) => Some(e),
+            PngyinxError::Palette(e) => Some(e),
+            PngyinxError::Text(e) => Some(e),
+            PngyinxError::Time(e) => Some(e),
+            PngyinxError::Color(e) => Some(e),
+            PngyinxError::Physical(e) => Some(e),
+            PngyinxError::Exif(e) => Some(e),
+            PngyinxError::Image(e) => Some(e),
+            PngyinxError::Crypto(e) => Some(e),
+            PngyinxError::Signature(e) => Some(e),
+            PngyinxError::Payload(e) => Some(e),
+            PngyinxError::Split(e) => Some(e),
+            PngyinxError::Strip(e) => Some(e),
+            PngyinxError::Io(e) => Some(e),
+        }
+    }
+}
+
+impl From<ChunkError> for PngyinxError {
+    fn from(value: ChunkError) -> Self {
+        PngyinxError::Chunk(value)
+    }
+}
+
+impl From<ChunkTypeDecodingError> for PngyinxError {
+    fn from(value: ChunkTypeDecodingError) -> Self {
+        PngyinxError::ChunkType(value)
+    }
+}
+
+impl From<PngError> for PngyinxError {
+    fn from(value: PngError) -> Self {
+        PngyinxError::Png(value)
+    }
+}
+
+impl From<IhdrError> for PngyinxError {
+    fn from(value: IhdrError) -> Self {
+        PngyinxError::Ihdr(value)
+    }
+}
+
+impl From<PaletteError> for PngyinxError {
+    fn from(value: PaletteError) -> Self {
+        PngyinxError::Palette(value)
+    }
+}
+
+impl From<TextError> for PngyinxError {
+    fn from(value: TextError) -> Self {
+        PngyinxError::Text(value)
+    }
+}
+
+impl From<TimeError> for PngyinxError {
+    fn from(value: TimeError) -> Self {
+        PngyinxError::Time(value)
+    }
+}
+
+impl From<ColorError> for PngyinxError {
+    fn from(value: ColorError) -> Self {
+        PngyinxError::Color(value)
+    }
+}
+
+impl From<PhysicalError> for PngyinxError {
+    fn from(value: PhysicalError) -> Self {
+        PngyinxError::Physical(value)
+    }
+}
+
+impl From<ExifError> for PngyinxError {
+    fn from(value: ExifError) -> Self {
+        PngyinxError::Exif(value)
+    }
+}
+
+impl From<ImageError> for PngyinxError {
+    fn from(value: ImageError) -> Self {
+        PngyinxError::Image(value)
+    }
+}
+
+impl From<CryptoError> for PngyinxError {
+    fn from(value: CryptoError) -> Self {
+        PngyinxError::Crypto(value)
+    }
+}
+
+impl From<SignatureError> for PngyinxError {
+    fn from(value: SignatureError) -> Self {
+        PngyinxError::Signature(value)
+    }
+}
+
+impl From<SplitError> for PngyinxError {
+    fn from(value: SplitError) -> Self {
+        PngyinxError::Split(value)
+    }
+}
+
+impl From<StripError> for PngyinxError {
+    fn from(value: StripError) -> Self {
+        PngyinxError::Strip(value)
+    }
+}
+
+impl From<PayloadError> for PngyinxError {
+    fn from(value: PayloadError) -> Self {
+        PngyinxError::Payload(value)
+    }
+}
+
+impl From<io::Error> for PngyinxError {
+    fn from(value: io::Error) -> Self {
+        PngyinxError::Io(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn test_error_source() {
+        let error = PngyinxError::from(PngError::IncorrectHeader);
+        assert!(error.source().is_some());
+        assert_eq!(error.source().unwrap().to_string(), PngError::IncorrectHeader.to_string());
+    }
+
+    #[test]
+    fn test_error_from_io() {
+        let error = PngyinxError::from(io::Error::new(io::ErrorKind::NotFound, "missing"));
+        assert!(matches!(error, PngyinxError::Io(_)));
+    }
+
+    #[test]
+    fn test_error_kind_and_code() {
+        let error = PngyinxError::from(SplitError::MissingFragments(vec![(1, 1)], 2));
+        assert_eq!(error.kind(), "split");
+        assert_eq!(error.code(), "missing_fragments");
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_error_to_json() {
+        let error = PngyinxError::from(PngError::IncorrectHeader);
+        assert_eq!(
+            serde_json::to_string(&error).unwrap(),
+            r#"{"kind":"png","code":"incorrect_header","message":"Invalid png: An incorrect header was found!"}"#,
+        );
+    }
+}
@@ -0,0 +1,950 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::exif::ExifError::{InvalidByteOrder, InvalidMagic, MalformedPointer, OutOfBounds, ReservedTag, TooLarge, WouldLose};
+use crate::Result;
+
+pub const EXIF_CHUNK_TYPE: &str = "eXIf";
+const HEADER_LENGTH: usize = 8;
+const ENTRY_LENGTH: usize = 12;
+
+pub const MAKE: u16 = 0x010f;
+pub const MODEL: u16 = 0x0110;
+pub const ORIENTATION: u16 = 0x0112;
+pub const DATE_TIME_ORIGINAL: u16 = 0x9003;
+pub const MAKER_NOTE: u16 = 0x927c;
+/// The offset and length of the thumbnail, in IFD1.
+const JPEG_INTERCHANGE_FORMAT: u16 = 0x0201;
+const JPEG_INTERCHANGE_FORMAT_LENGTH: u16 = 0x0202;
+pub const GPS_LATITUDE_REF: u16 = 0x0001;
+pub const GPS_LATITUDE: u16 = 0x0002;
+pub const GPS_LONGITUDE_REF: u16 = 0x0003;
+pub const GPS_LONGITUDE: u16 = 0x0004;
+
+/// The tags that point to another IFD, as (parent, tag, child).
+const POINTERS: [(Ifd, u16, Ifd); 3] = [
+    (Ifd::Primary, 0x8769, Ifd::Exif),
+    (Ifd::Primary, 0x8825, Ifd::Gps),
+    (Ifd::Exif, 0xa005, Ifd::Interoperability),
+];
+
+/// The names of common tags, for display.
+const TAG_NAMES: [(Ifd, u16, &str); 38] = [
+    (Ifd::Primary, 0x010e, "ImageDescription"),
+    (Ifd::Primary, MAKE, "Make"),
+    (Ifd::Primary, MODEL, "Model"),
+    (Ifd::Primary, ORIENTATION, "Orientation"),
+    (Ifd::Primary, 0x011a, "XResolution"),
+    (Ifd::Primary, 0x011b, "YResolution"),
+    (Ifd::Primary, 0x0128, "ResolutionUnit"),
+    (Ifd::Primary, 0x0131, "Software"),
+    (Ifd::Primary, 0x0132, "DateTime"),
+    (Ifd::Primary, 0x013b, "Artist"),
+    (Ifd::Primary, 0x0213, "YCbCrPositioning"),
+    (Ifd::Primary, 0x8298, "Copyright"),
+    (Ifd::Exif, 0x829a, "ExposureTime"),
+    (Ifd::Exif, 0x829d, "FNumber"),
+    (Ifd::Exif, 0x8827, "ISOSpeedRatings"),
+    (Ifd::Exif, 0x9000, "ExifVersion"),
+    (Ifd::Exif, DATE_TIME_ORIGINAL, "DateTimeOriginal"),
+    (Ifd::Exif, 0x9004, "DateTimeDigitized"),
+    (Ifd::Exif, 0x9010, "OffsetTime"),
+    (Ifd::Exif, 0x9011, "OffsetTimeOriginal"),
+    (Ifd::Exif, 0x9209, "Flash"),
+    (Ifd::Exif, 0x920a, "FocalLength"),
+    (Ifd::Exif, MAKER_NOTE, "MakerNote"),
+    (Ifd::Exif, 0x9286, "UserComment"),
+    (Ifd::Exif, 0xa001, "ColorSpace"),
+    (Ifd::Exif, 0xa002, "PixelXDimension"),
+    (Ifd::Exif, 0xa003, "PixelYDimension"),
+    (Ifd::Exif, 0xa433, "LensMake"),
+    (Ifd::Exif, 0xa434, "LensModel"),
+    (Ifd::Gps, 0x0000, "GPSVersionID"),
+    (Ifd::Gps, GPS_LATITUDE_REF, "GPSLatitudeRef"),
+    (Ifd::Gps, GPS_LATITUDE, "GPSLatitude"),
+    (Ifd::Gps, GPS_LONGITUDE_REF, "GPSLongitudeRef"),
+    (Ifd::Gps, GPS_LONGITUDE, "GPSLongitude"),
+    (Ifd::Gps, 0x0005, "GPSAltitudeRef"),
+    (Ifd::Gps, 0x0006, "GPSAltitude"),
+    (Ifd::Gps, 0x0007, "GPSTimeStamp"),
+    (Ifd::Gps, 0x001d, "GPSDateStamp"),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "snake_case"))]
+pub enum ByteOrder {
+    LittleEndian,
+    BigEndian,
+}
+
+impl ByteOrder {
+    fn u16(self, bytes: &[u8]) -> u16 {
+        let bytes = [bytes[0], bytes[1]];
+        match self {
+            ByteOrder::LittleEndian => u16::from_le_bytes(bytes),
+            ByteOrder::BigEndian => u16::from_be_bytes(bytes),
+        }
+    }
+
+    fn u32(self, bytes: &[u8]) -> u32 {
+        let bytes = bytes[..4].try_into().unwrap();
+        match self {
+            ByteOrder::LittleEndian => u32::from_le_bytes(bytes),
+            ByteOrder::BigEndian => u32::from_be_bytes(bytes),
+        }
+    }
+
+    fn u64(self, bytes: &[u8]) -> u64 {
+        let bytes = bytes[..8].try_into().unwrap();
+        match self {
+            ByteOrder::LittleEndian => u64::from_le_bytes(bytes),
+            ByteOrder::BigEndian => u64::from_be_bytes(bytes),
+        }
+    }
+
+    fn u16_bytes(self, value: u16) -> [u8; 2] {
+        match self {
+            ByteOrder::LittleEndian => value.to_le_bytes(),
+            ByteOrder::BigEndian => value.to_be_bytes(),
+        }
+    }
+
+    fn u32_bytes(self, value: u32) -> [u8; 4] {
+        match self {
+            ByteOrder::LittleEndian => value.to_le_bytes(),
+            ByteOrder::BigEndian => value.to_be_bytes(),
+        }
+    }
+
+    fn u64_bytes(self, value: u64) -> [u8; 8] {
+        match self {
+            ByteOrder::LittleEndian => value.to_le_bytes(),
+            ByteOrder::BigEndian => value.to_be_bytes(),
+        }
+    }
+}
+
+/// The directories of tags that are read. IFD1, which describes the thumbnail, is left out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "snake_case"))]
+pub enum Ifd {
+    /// IFD0, about the main image.
+    Primary,
+    Exif,
+    Gps,
+    Interoperability,
+}
+
+impl Display for Ifd {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Ifd::Primary => write!(f, "IFD0"),
+            Ifd::Exif => write!(f, "Exif"),
+            Ifd::Gps => write!(f, "GPS"),
+            Ifd::Interoperability => write!(f, "Interop"),
+        }
+    }
+}
+
+impl Ifd {
+    /// In the order they are written.
+    const ALL: [Ifd; 4] = [Ifd::Primary, Ifd::Exif, Ifd::Gps, Ifd::Interoperability];
+
+    fn parent(self) -> Option<Ifd> {
+        POINTERS.iter().find(|(_, _, child)| *child == self).map(|(parent, _, _)| *parent)
+    }
+
+    /// Whether `self` is `other` or one of its descendants.
+    fn is_within(self, other: Ifd) -> bool {
+        self == other || self.parent().is_some_and(|parent| parent.is_within(other))
+    }
+}
+
+/// The IFD that `tag` points to when found in `ifd`.
+fn child(ifd: Ifd, tag: u16) -> Option<Ifd> {
+    POINTERS.iter().find(|(parent, pointer, _)| *parent == ifd && *pointer == tag).map(|(_, _, child)| *child)
+}
+
+/// The size in bytes of one value of a TIFF field type.
+fn type_size(field_type: u16) -> Option<usize> {
+    match field_type {
+        1 | 2 | 6 | 7 => Some(1),
+        3 | 8 => Some(2),
+        4 | 9 | 11 | 13 => Some(4),
+        5 | 10 | 12 => Some(8),
+        _ => None,
+    }
+}
+
+/// The value of a tag, by TIFF field type.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "snake_case"))]
+pub enum Value {
+    Byte(Vec<u8>),
+    /// Without its terminating null byte.
+    Ascii(String),
+    Short(Vec<u16>),
+    Long(Vec<u32>),
+    Rational(Vec<(u32, u32)>),
+    SByte(Vec<i8>),
+    Undefined(Vec<u8>),
+    SShort(Vec<i16>),
+    SLong(Vec<i32>),
+    SRational(Vec<(i32, i32)>),
+    Float(Vec<f32>),
+    Double(Vec<f64>),
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fn join<T: ToString>(values: impl Iterator<Item = T>) -> String {
+            values.map(|value| value.to_string()).collect::<Vec<_>>().join(", ")
+        }
+        match self {
+            Value::Byte(values) => write!(f, "{}", join(values.iter())),
+            Value::Ascii(text) => write!(f, "{}", text),
+            Value::Short(values) => write!(f, "{}", join(values.iter())),
+            Value::Long(values) => write!(f, "{}", join(values.iter())),
+            Value::Rational(values) => write!(f, "{}", join(values.iter().map(|(numerator, denominator)| format!("{}/{}", numerator, denominator)))),
+            Value::SByte(values) => write!(f, "{}", join(values.iter())),
+            Value::Undefined(bytes) if bytes.len() <= 16 => write!(f, "0x{}", hex::encode(bytes)),
+            Value::Undefined(bytes) => write!(f, "{} bytes", bytes.len()),
+            Value::SShort(values) => write!(f, "{}", join(values.iter())),
+            Value::SLong(values) => write!(f, "{}", join(values.iter())),
+            Value::SRational(values) => write!(f, "{}", join(values.iter().map(|(numerator, denominator)| format!("{}/{}", numerator, denominator)))),
+            Value::Float(values) => write!(f, "{}", join(values.iter())),
+            Value::Double(values) => write!(f, "{}", join(values.iter())),
+        }
+    }
+}
+
+impl Value {
+    fn from_bytes(field_type: u16, bytes: &[u8], order: ByteOrder) -> Option<Value> {
+        let shorts = || bytes.chunks(2).map(|short| order.u16(short));
+        let longs = || bytes.chunks(4).map(|long| order.u32(long));
+        let value = match field_type {
+            1 => Value::Byte(bytes.to_vec()),
+            2 => Value::Ascii(String::from_utf8_lossy(bytes).trim_end_matches('\0').to_string()),
+            3 => Value::Short(shorts().collect()),
+            4 => Value::Long(longs().collect()),
+            5 => Value::Rational(bytes.chunks(8).map(|pair| (order.u32(pair), order.u32(&pair[4..]))).collect()),
+            6 => Value::SByte(bytes.iter().map(|&byte| byte as i8).collect()),
+            7 => Value::Undefined(bytes.to_vec()),
+            8 => Value::SShort(shorts().map(|short| short as i16).collect()),
+            9 => Value::SLong(longs().map(|long| long as i32).collect()),
+            10 => Value::SRational(bytes.chunks(8).map(|pair| (order.u32(pair) as i32, order.u32(&pair[4..]) as i32)).collect()),
+            11 => Value::Float(longs().map(f32::from_bits).collect()),
+            12 => Value::Double(bytes.chunks(8).map(|double| f64::from_bits(order.u64(double))).collect()),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// The TIFF field type.
+    pub fn field_type(&self) -> u16 {
+        match self {
+            Value::Byte(_) => 1,
+            Value::Ascii(_) => 2,
+            Value::Short(_) => 3,
+            Value::Long(_) => 4,
+            Value::Rational(_) => 5,
+            Value::SByte(_) => 6,
+            Value::Undefined(_) => 7,
+            Value::SShort(_) => 8,
+            Value::SLong(_) => 9,
+            Value::SRational(_) => 10,
+            Value::Float(_) => 11,
+            Value::Double(_) => 12,
+        }
+    }
+
+    fn to_bytes(&self, order: ByteOrder) -> Vec<u8> {
+        match self {
+            Value::Byte(bytes) | Value::Undefined(bytes) => bytes.clone(),
+            Value::Ascii(text) => text.bytes().chain([0]).collect(),
+            Value::Short(values) => values.iter().flat_map(|&value| order.u16_bytes(value)).collect(),
+            Value::Long(values) => values.iter().flat_map(|&value| order.u32_bytes(value)).collect(),
+            Value::Rational(values) => values.iter()
+                .flat_map(|&(numerator, denominator)| [order.u32_bytes(numerator), order.u32_bytes(denominator)])
+                .flatten()
+                .collect(),
+            Value::SByte(values) => values.iter().map(|&value| value as u8).collect(),
+            Value::SShort(values) => values.iter().flat_map(|&value| order.u16_bytes(value as u16)).collect(),
+            Value::SLong(values) => values.iter().flat_map(|&value| order.u32_bytes(value as u32)).collect(),
+            Value::SRational(values) => values.iter()
+                .flat_map(|&(numerator, denominator)| [order.u32_bytes(numerator as u32), order.u32_bytes(denominator as u32)])
+                .flatten()
+                .collect(),
+            Value::Float(values) => values.iter().flat_map(|value| order.u32_bytes(value.to_bits())).collect(),
+            Value::Double(values) => values.iter().flat_map(|value| order.u64_bytes(value.to_bits())).collect(),
+        }
+    }
+
+    fn as_degrees(&self) -> Option<f64> {
+        match self {
+            Value::Rational(values) if values.len() == 3 && values.iter().all(|&(_, denominator)| denominator != 0) => {
+                let [degrees, minutes, seconds] = [0, 1, 2].map(|index| values[index].0 as f64 / values[index].1 as f64);
+                Some(degrees + minutes / 60.0 + seconds / 3600.0)
+            },
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Entry {
+    pub ifd: Ifd,
+    pub tag: u16,
+    pub value: Value,
+}
+
+impl Entry {
+    /// The name of the tag, if it is a common one.
+    pub fn name(&self) -> Option<&'static str> {
+        TAG_NAMES.iter().find(|(ifd, tag, _)| *ifd == self.ifd && *tag == self.tag).map(|(_, _, name)| *name)
+    }
+}
+
+/// What is read but can't be written back, as rewriting the data moves every value around.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "snake_case"))]
+pub enum Unsupported {
+    /// IFD1 and the thumbnail it describes.
+    Thumbnail,
+    /// A field of an unknown type, or of the IFD type, as (IFD, tag, field type).
+    Field(Ifd, u16, u16),
+    /// A tag that should point to another IFD but isn't a single offset.
+    MalformedPointer(Ifd, u16),
+    /// The MakerNote, whose contents hold offsets that moving it would break.
+    MakerNote,
+    /// An ASCII field that isn't valid UTF-8, or is padded with several null bytes, and so
+    /// wouldn't be written back as it was, as (IFD, tag).
+    Ascii(Ifd, u16),
+}
+
+impl Display for Unsupported {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Unsupported::Thumbnail => write!(f, "the thumbnail in IFD1"),
+            Unsupported::Field(ifd, tag, field_type) => write!(f, "the {} field {:#06x} of type {}", ifd, tag, field_type),
+            Unsupported::MalformedPointer(ifd, tag) => write!(f, "the malformed {} pointer {:#06x}", ifd, tag),
+            Unsupported::MakerNote => write!(f, "the MakerNote"),
+            Unsupported::Ascii(ifd, tag) => write!(f, "the exact bytes of the {} text {:#06x}", ifd, tag),
+        }
+    }
+}
+
+/// The EXIF metadata of an eXIf chunk: a TIFF header followed by IFD0 and the Exif, GPS and
+/// Interoperability IFDs it points to. The tags pointing to those IFDs aren't kept as entries,
+/// they are rebuilt on writing. What can't be rebuilt is listed by `unsupported`, and `to_data`
+/// refuses to lose it unless `drop_unsupported` is called first.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Exif {
+    byte_order: ByteOrder,
+    entries: Vec<Entry>,
+    unsupported: Vec<Unsupported>,
+}
+
+impl Exif {
+    pub fn new(byte_order: ByteOrder) -> Exif {
+        Exif { byte_order, entries: Vec::new(), unsupported: Vec::new() }
+    }
+
+    pub fn from_data(data: &[u8]) -> Result<Exif> {
+        let header = slice(data, 0, HEADER_LENGTH)?;
+        let byte_order = match &header[..2] {
+            b"II" => ByteOrder::LittleEndian,
+            b"MM" => ByteOrder::BigEndian,
+            _ => return Err(InvalidByteOrder([header[0], header[1]]).into()),
+        };
+        let magic = byte_order.u16(&header[2..4]);
+        if magic != 42 {
+            return Err(InvalidMagic(magic).into());
+        }
+        let mut exif = Exif::new(byte_order);
+        // Entries may share their values, so the values copied out are capped at the size of the data.
+        let mut budget = data.len();
+        exif.read_ifd(data, Ifd::Primary, byte_order.u32(&header[4..8]) as usize, &mut vec![Ifd::Primary], &mut budget)?;
+        Ok(exif)
+    }
+
+    fn read_ifd(&mut self, data: &[u8], ifd: Ifd, offset: usize, visited: &mut Vec<Ifd>, budget: &mut usize) -> Result<()> {
+        let order = self.byte_order;
+        let count = order.u16(slice(data, offset, 2)?) as usize;
+        for index in 0..count {
+            let start = offset + 2 + index * ENTRY_LENGTH;
+            let entry = slice(data, start, ENTRY_LENGTH)?;
+            let tag = order.u16(&entry[0..2]);
+            let field_type = order.u16(&entry[2..4]);
+            // Fields of unknown types can't be sized and are skipped, as TIFF readers should.
+            let Some(size) = type_size(field_type) else {
+                self.unsupported.push(Unsupported::Field(ifd, tag, field_type));
+                continue;
+            };
+            let length = size.checked_mul(order.u32(&entry[4..8]) as usize).ok_or(OutOfBounds(start))?;
+            let bytes = if length <= 4 {
+                &entry[8..8 + length]
+            } else {
+                *budget = budget.checked_sub(length).ok_or(TooLarge)?;
+                slice(data, order.u32(&entry[8..12]) as usize, length)?
+            };
+            match child(ifd, tag) {
+                Some(child) if length == 4 && !visited.contains(&child) => {
+                    visited.push(child);
+                    self.read_ifd(data, child, order.u32(bytes) as usize, visited, budget)?;
+                },
+                Some(_) if length == 4 => {},
+                Some(_) => self.unsupported.push(Unsupported::MalformedPointer(ifd, tag)),
+                None if field_type == 13 => self.unsupported.push(Unsupported::Field(ifd, tag, field_type)),
+                None => {
+                    if ifd == Ifd::Exif && tag == MAKER_NOTE {
+                        self.unsupported.push(Unsupported::MakerNote);
+                    }
+                    if field_type == 2 && !is_plain_ascii(bytes) {
+                        self.unsupported.push(Unsupported::Ascii(ifd, tag));
+                    }
+                    if let Some(value) = Value::from_bytes(field_type, bytes, order) {
+                        self.entries.push(Entry { ifd, tag, value });
+                    }
+                },
+            }
+        }
+        if ifd == Ifd::Primary {
+            let next = offset + 2 + count * ENTRY_LENGTH;
+            if slice(data, next, 4).is_ok_and(|next| order.u32(next) != 0) {
+                self.unsupported.push(Unsupported::Thumbnail);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn byte_order(&self) -> ByteOrder {
+        self.byte_order
+    }
+
+    /// The entries in the order they were read or added.
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// What was read but would be lost by `to_data`.
+    pub fn unsupported(&self) -> &[Unsupported] {
+        &self.unsupported
+    }
+
+    /// Accepts losing what `unsupported` lists, so that `to_data` stops refusing. The MakerNote
+    /// goes too, as it would be broken once moved.
+    pub fn drop_unsupported(&mut self) {
+        if self.unsupported.contains(&Unsupported::MakerNote) {
+            self.remove(Ifd::Exif, MAKER_NOTE);
+        }
+        self.unsupported.clear();
+    }
+
+    pub fn get(&self, ifd: Ifd, tag: u16) -> Option<&Value> {
+        self.entries.iter().find(|entry| entry.ifd == ifd && entry.tag == tag).map(|entry| &entry.value)
+    }
+
+    /// Sets a tag, replacing its value if it is already there. The tags that point to other IFDs
+    /// can't be set, they follow from the entries.
+    pub fn set(&mut self, ifd: Ifd, tag: u16, value: Value) -> Result<()> {
+        if child(ifd, tag).is_some() {
+            return Err(ReservedTag(tag).into());
+        }
+        match self.entries.iter_mut().find(|entry| entry.ifd == ifd && entry.tag == tag) {
+            Some(entry) => entry.value = value,
+            None => self.entries.push(Entry { ifd, tag, value }),
+        }
+        Ok(())
+    }
+
+    pub fn remove(&mut self, ifd: Ifd, tag: u16) -> Option<Value> {
+        let index = self.entries.iter().position(|entry| entry.ifd == ifd && entry.tag == tag)?;
+        Some(self.entries.remove(index).value)
+    }
+
+    /// Removes the entries of `ifd` and of the IFDs it points to, and returns them.
+    pub fn remove_ifd(&mut self, ifd: Ifd) -> Vec<Entry> {
+        let (removed, kept) = self.entries.drain(..).partition(|entry| entry.ifd.is_within(ifd));
+        self.entries = kept;
+        removed
+    }
+
+    /// Removes every GPS tag and returns them.
+    pub fn remove_gps(&mut self) -> Vec<Entry> {
+        self.remove_ifd(Ifd::Gps)
+    }
+
+    /// How the image should be rotated or flipped, from 1 to 8.
+    pub fn orientation(&self) -> Option<u16> {
+        match self.get(Ifd::Primary, ORIENTATION) {
+            Some(Value::Short(values)) => values.first().copied(),
+            _ => None,
+        }
+    }
+
+    pub fn make(&self) -> Option<&str> {
+        self.ascii(Ifd::Primary, MAKE)
+    }
+
+    pub fn model(&self) -> Option<&str> {
+        self.ascii(Ifd::Primary, MODEL)
+    }
+
+    /// When the photo was taken, as written by the camera, e.g. "2024:06:01 12:00:00".
+    pub fn date_time_original(&self) -> Option<&str> {
+        self.ascii(Ifd::Exif, DATE_TIME_ORIGINAL)
+    }
+
+    /// The latitude and longitude in degrees, negative to the south and west.
+    pub fn gps_coordinates(&self) -> Option<(f64, f64)> {
+        let coordinate = |tag, reference_tag, negative| {
+            let degrees = self.get(Ifd::Gps, tag)?.as_degrees()?;
+            Some(if self.ascii(Ifd::Gps, reference_tag) == Some(negative) { -degrees } else { degrees })
+        };
+        Some((coordinate(GPS_LATITUDE, GPS_LATITUDE_REF, "S")?, coordinate(GPS_LONGITUDE, GPS_LONGITUDE_REF, "W")?))
+    }
+
+    fn ascii(&self, ifd: Ifd, tag: u16) -> Option<&str> {
+        match self.get(ifd, tag) {
+            Some(Value::Ascii(text)) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Whether `ifd` has to be written: it has entries or points to an IFD that does.
+    fn is_present(&self, ifd: Ifd) -> bool {
+        ifd == Ifd::Primary
+            || self.entries.iter().any(|entry| entry.ifd == ifd)
+            || POINTERS.iter().any(|(parent, _, child)| *parent == ifd && self.is_present(*child))
+    }
+
+    /// The fields of `ifd` sorted by tag as TIFF requires, with the pointers to other IFDs.
+    fn fields(&self, ifd: Ifd) -> Vec<Field> {
+        let order = self.byte_order;
+        let mut fields: Vec<Field> = self.entries.iter()
+            .filter(|entry| entry.ifd == ifd)
+            .map(|entry| {
+                let bytes = entry.value.to_bytes(order);
+                let count = bytes.len() / type_size(entry.value.field_type()).unwrap();
+                Field { tag: entry.tag, field_type: entry.value.field_type(), count: count as u32, bytes, pointer: None }
+            })
+            .collect();
+        for (_, tag, child) in POINTERS.iter().filter(|(parent, _, child)| *parent == ifd && self.is_present(*child)) {
+            fields.push(Field { tag: *tag, field_type: 4, count: 1, bytes: vec![0; 4], pointer: Some(*child) });
+        }
+        fields.sort_by_key(|field| field.tag);
+        fields
+    }
+
+    /// Writes the TIFF structure: the header, then each IFD followed by the values that don't
+    /// fit in its entries. Fails if something listed by `unsupported` would be lost.
+    pub fn to_data(&self) -> Result<Vec<u8>> {
+        if !self.unsupported.is_empty() {
+            return Err(WouldLose(self.unsupported.clone()).into());
+        }
+        let order = self.byte_order;
+        let ifds: Vec<Ifd> = Ifd::ALL.into_iter().filter(|&ifd| self.is_present(ifd)).collect();
+        let tables: Vec<Vec<Field>> = ifds.iter().map(|&ifd| self.fields(ifd)).collect();
+        let mut offsets = Vec::with_capacity(tables.len());
+        let mut offset = HEADER_LENGTH;
+        for table in &tables {
+            offsets.push(offset);
+            offset += 2 + table.len() * ENTRY_LENGTH + 4 + table.iter().map(Field::external_length).sum::<usize>();
+        }
+        let offset_of = |ifd: Ifd| offsets[ifds.iter().position(|&other| other == ifd).unwrap()] as u32;
+
+        let mut data = match order {
+            ByteOrder::LittleEndian => b"II".to_vec(),
+            ByteOrder::BigEndian => b"MM".to_vec(),
+        };
+        data.extend_from_slice(&order.u16_bytes(42));
+        data.extend_from_slice(&order.u32_bytes(HEADER_LENGTH as u32));
+        for (table, &start) in tables.iter().zip(&offsets) {
+            let values_start = start + 2 + table.len() * ENTRY_LENGTH + 4;
+            let mut values = Vec::new();
+            data.extend_from_slice(&order.u16_bytes(table.len() as u16));
+            for field in table {
+                data.extend_from_slice(&order.u16_bytes(field.tag));
+                data.extend_from_slice(&order.u16_bytes(field.field_type));
+                data.extend_from_slice(&order.u32_bytes(field.count));
+                if let Some(child) = field.pointer {
+                    data.extend_from_slice(&order.u32_bytes(offset_of(child)));
+                } else if field.bytes.len() <= 4 {
+                    data.extend_from_slice(&field.bytes);
+                    data.resize(data.len() + 4 - field.bytes.len(), 0);
+                } else {
+                    data.extend_from_slice(&order.u32_bytes((values_start + values.len()) as u32));
+                    values.extend_from_slice(&field.bytes);
+                    // Values start on word boundaries.
+                    values.resize(values.len().next_multiple_of(2), 0);
+                }
+            }
+            // No IFD1.
+            data.extend_from_slice(&order.u32_bytes(0));
+            data.extend(values);
+        }
+        Ok(data)
+    }
+
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        Chunk::try_new(ChunkType::from_str(EXIF_CHUNK_TYPE)?, self.to_data()?)
+    }
+}
+
+/// Removes the GPS IFD from the raw data of an eXIf chunk without moving anything else, so that
+/// what `Exif` can't write back, like the thumbnail or the MakerNote, is kept. The pointer to the
+/// IFD is taken out of IFD0, and the IFD and the values of its fields are zeroed, except where
+/// something outside the GPS IFD refers to the same bytes.
+/// Returns how many GPS fields were removed.
+pub fn erase_gps(data: &mut [u8]) -> Result<usize> {
+    let order = Exif::from_data(data)?.byte_order;
+    let ifd0 = order.u32(&data[4..8]) as usize;
+    let count = order.u16(slice(data, ifd0, 2)?) as usize;
+    let table = slice(data, ifd0 + 2, count * ENTRY_LENGTH + 4)?;
+    let Some(index) = (0..count).find(|&index| child(Ifd::Primary, order.u16(&table[index * ENTRY_LENGTH..])) == Some(Ifd::Gps)) else {
+        return Ok(0);
+    };
+    // The same rule as `read_ifd`: a pointer is a single offset.
+    let pointer = &table[index * ENTRY_LENGTH..(index + 1) * ENTRY_LENGTH];
+    let length = type_size(order.u16(&pointer[2..4])).and_then(|size| size.checked_mul(order.u32(&pointer[4..8]) as usize));
+    if length != Some(4) {
+        return Err(MalformedPointer(order.u16(&pointer[0..2])).into());
+    }
+    let gps = order.u32(&pointer[8..12]) as usize;
+    let gps_count = order.u16(slice(data, gps, 2)?) as usize;
+    let mut erased = vec![(gps, 2 + gps_count * ENTRY_LENGTH + 4)];
+    for entry in slice(data, gps + 2, gps_count * ENTRY_LENGTH)?.chunks(ENTRY_LENGTH) {
+        let length = type_size(order.u16(&entry[2..4])).and_then(|size| size.checked_mul(order.u32(&entry[4..8]) as usize));
+        if let Some(length) = length.filter(|&length| length > 4) {
+            erased.push((order.u32(&entry[8..12]) as usize, length));
+        }
+    }
+    for &(start, length) in &erased {
+        slice(data, start, length)?;
+    }
+    let mut kept = Vec::new();
+    referenced_ranges(data, order, ifd0, true, &mut vec![ifd0], &mut kept)?;
+    erased.retain(|&range| !kept.iter().any(|&other| overlaps(range, other)));
+
+    // The entries after the pointer and the offset of IFD1 move up, the freed space is zeroed.
+    let start = ifd0 + 2 + index * ENTRY_LENGTH;
+    let end = ifd0 + 2 + count * ENTRY_LENGTH + 4;
+    data.copy_within(start + ENTRY_LENGTH..end, start);
+    data[end - ENTRY_LENGTH..end].fill(0);
+    data[ifd0..ifd0 + 2].copy_from_slice(&order.u16_bytes(count as u16 - 1));
+    for (start, length) in erased {
+        data[start..start + length].fill(0);
+    }
+    Ok(gps_count)
+}
+
+/// Collects the ranges of `data` that the IFD at `offset` refers to, its table included, then
+/// those of the IFDs it points to, GPS aside. IFD0 also leads to IFD1 and its thumbnail.
+fn referenced_ranges(data: &[u8], order: ByteOrder, offset: usize, is_ifd0: bool, visited: &mut Vec<usize>, ranges: &mut Vec<(usize, usize)>) -> Result<()> {
+    let count = order.u16(slice(data, offset, 2)?) as usize;
+    ranges.push((offset, 2 + count * ENTRY_LENGTH + 4));
+    let mut thumbnail = (None, None);
+    let mut children = Vec::new();
+    for entry in slice(data, offset + 2, count * ENTRY_LENGTH)?.chunks(ENTRY_LENGTH) {
+        let tag = order.u16(&entry[0..2]);
+        let value = order.u32(&entry[8..12]) as usize;
+        match tag {
+            JPEG_INTERCHANGE_FORMAT => thumbnail.0 = Some(value),
+            JPEG_INTERCHANGE_FORMAT_LENGTH => thumbnail.1 = Some(value),
+            _ => {},
+        }
+        let Some(length) = type_size(order.u16(&entry[2..4])).and_then(|size| size.checked_mul(order.u32(&entry[4..8]) as usize)) else {
+            continue;
+        };
+        if length > 4 {
+            ranges.push((value, length));
+        }
+        let is_pointer = POINTERS.iter().any(|(_, pointer, child)| *pointer == tag && *child != Ifd::Gps);
+        if is_pointer && length == 4 && !visited.contains(&value) {
+            visited.push(value);
+            children.push(value);
+        }
+    }
+    if let (Some(start), Some(length)) = thumbnail {
+        ranges.push((start, length));
+    }
+    if is_ifd0 {
+        let next = order.u32(slice(data, offset + 2 + count * ENTRY_LENGTH, 4)?) as usize;
+        if next != 0 && !visited.contains(&next) {
+            visited.push(next);
+            children.push(next);
+        }
+    }
+    for child in children {
+        referenced_ranges(data, order, child, false, visited, ranges)?;
+    }
+    Ok(())
+}
+
+fn overlaps((start, length): (usize, usize), (other_start, other_length): (usize, usize)) -> bool {
+    start < other_start.saturating_add(other_length) && other_start < start.saturating_add(length)
+}
+
+/// Whether an ASCII value reads as UTF-8 and ends with at most one null byte, so that
+/// `Value::Ascii` writes it back unchanged, but for a missing terminator.
+fn is_plain_ascii(bytes: &[u8]) -> bool {
+    let text = bytes.strip_suffix(&[0]).unwrap_or(bytes);
+    !text.contains(&0) && std::str::from_utf8(text).is_ok()
+}
+
+/// An entry as written, with its value encoded.
+struct Field {
+    tag: u16,
+    field_type: u16,
+    count: u32,
+    bytes: Vec<u8>,
+    pointer: Option<Ifd>,
+}
+
+impl Field {
+    /// The space the value takes after the IFD when it doesn't fit in the entry.
+    fn external_length(&self) -> usize {
+        if self.bytes.len() <= 4 { 0 } else { self.bytes.len().next_multiple_of(2) }
+    }
+}
+
+fn slice(data: &[u8], offset: usize, length: usize) -> Result<&[u8]> {
+    offset.checked_add(length)
+        .and_then(|end| data.get(offset..end))
+        .ok_or(OutOfBounds(offset).into())
+}
+
+#[derive(Debug)]
+pub enum ExifError {
+    InvalidByteOrder([u8; 2]),
+    InvalidMagic(u16),
+    OutOfBounds(usize),
+    ReservedTag(u16),
+    MalformedPointer(u16),
+    TooLarge,
+    WouldLose(Vec<Unsupported>),
+}
+
+impl Display for ExifError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvalidByteOrder(bytes) => write!(f, "Unknown byte order: {:?}", bytes),
+            InvalidMagic(magic) => write!(f, "Expected the TIFF number 42 but found {}", magic),
+            OutOfBounds(offset) => write!(f, "The data at offset {} is past the end", offset),
+            ReservedTag(tag) => write!(f, "The tag {:#06x} points to another IFD and can't be set", tag),
+            MalformedPointer(tag) => write!(f, "The tag {:#06x} should point to another IFD but isn't a single offset", tag),
+            TooLarge => write!(f, "The values of the entries add up to more than the data"),
+            WouldLose(unsupported) => {
+                let unsupported: Vec<String> = unsupported.iter().map(Unsupported::to_string).collect();
+                write!(f, "Rewriting the EXIF metadata would lose {}", unsupported.join(", "))
+            },
+        }
+    }
+}
+
+impl ExifError {
+    /// A stable identifier for the error, for machine-readable output.
+    pub fn code(&self) -> &'static str {
+        match self {
+            InvalidByteOrder(_) => "invalid_byte_order",
+            InvalidMagic(_) => "invalid_magic",
+            OutOfBounds(_) => "out_of_bounds",
+            ReservedTag(_) => "reserved_tag",
+            MalformedPointer(_) => "malformed_pointer",
+            TooLarge => "too_large",
+            WouldLose(_) => "would_lose",
+        }
+    }
+}
+
+impl std::error::Error for ExifError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::Png;
+    use crate::PngyinxError;
+
+    const PNG_FILE: &[u8] = include_bytes!("../pictures/dice.png");
+
+    fn exif(byte_order: ByteOrder) -> Exif {
+        let mut exif = Exif::new(byte_order);
+        exif.set(Ifd::Primary, MAKE, Value::Ascii("Pngyinx".to_string())).unwrap();
+        exif.set(Ifd::Primary, ORIENTATION, Value::Short(vec![6])).unwrap();
+        exif.set(Ifd::Exif, DATE_TIME_ORIGINAL, Value::Ascii("2024:06:01 12:00:00".to_string())).unwrap();
+        exif.set(Ifd::Gps, GPS_LATITUDE_REF, Value::Ascii("S".to_string())).unwrap();
+        exif.set(Ifd::Gps, GPS_LATITUDE, Value::Rational(vec![(33, 1), (30, 1), (0, 1)])).unwrap();
+        exif.set(Ifd::Gps, GPS_LONGITUDE_REF, Value::Ascii("E".to_string())).unwrap();
+        exif.set(Ifd::Gps, GPS_LONGITUDE, Value::Rational(vec![(151, 1), (15, 1), (0, 1)])).unwrap();
+        exif
+    }
+
+    #[test]
+    fn test_parse_big_endian() {
+        // IFD0 with Orientation = 3 and Make = "Canon", stored after the IFD.
+        let data = [
+            b"MM".as_slice(), &[0, 42], &[0, 0, 0, 8],
+            &[0, 2],
+            &[0x01, 0x0f, 0, 2, 0, 0, 0, 6, 0, 0, 0, 38],
+            &[0x01, 0x12, 0, 3, 0, 0, 0, 1, 0, 3, 0, 0],
+            &[0, 0, 0, 0],
+            b"Canon\0",
+        ].concat();
+        let exif = Exif::from_data(&data).unwrap();
+        assert_eq!(exif.byte_order(), ByteOrder::BigEndian);
+        assert_eq!(exif.orientation(), Some(3));
+        assert_eq!(exif.make(), Some("Canon"));
+        assert_eq!(exif.entries()[0].name(), Some("Make"));
+        assert_eq!(exif.to_data().unwrap(), data);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for byte_order in [ByteOrder::LittleEndian, ByteOrder::BigEndian] {
+            let exif = exif(byte_order);
+            let parsed = Exif::from_data(&exif.to_data().unwrap()).unwrap();
+            assert_eq!(parsed.orientation(), Some(6));
+            assert_eq!(parsed.date_time_original(), Some("2024:06:01 12:00:00"));
+            assert_eq!(parsed.gps_coordinates(), Some((-33.5, 151.25)));
+            assert_eq!(parsed.entries().len(), exif.entries().len());
+        }
+    }
+
+    #[test]
+    fn test_remove_gps() {
+        let mut exif = exif(ByteOrder::LittleEndian);
+        assert_eq!(exif.remove_gps().len(), 4);
+        let data = exif.to_data().unwrap();
+        assert!(!data.windows(2).any(|window| window == [0x25, 0x88]));
+        let parsed = Exif::from_data(&data).unwrap();
+        assert_eq!(parsed.gps_coordinates(), None);
+        assert_eq!(parsed.entries().len(), 3);
+        assert_eq!(exif.remove(Ifd::Primary, ORIENTATION), Some(Value::Short(vec![6])));
+        assert!(matches!(exif.set(Ifd::Primary, 0x8825, Value::Long(vec![0])), Err(PngyinxError::Exif(ReservedTag(0x8825)))));
+    }
+
+    #[test]
+    fn test_invalid_exif() {
+        assert!(matches!(Exif::from_data(b"XX\0*\0\0\0\x08"), Err(PngyinxError::Exif(InvalidByteOrder(_)))));
+        assert!(matches!(Exif::from_data(b"II\x2b\0\x08\0\0\0"), Err(PngyinxError::Exif(InvalidMagic(43)))));
+        assert!(matches!(Exif::from_data(b"II*\0\xff\0\0\0"), Err(PngyinxError::Exif(OutOfBounds(255)))));
+        let mut data = exif(ByteOrder::LittleEndian).to_data().unwrap();
+        data.truncate(data.len() - 10);
+        assert!(matches!(Exif::from_data(&data), Err(PngyinxError::Exif(OutOfBounds(_)))));
+
+        // Every entry points at the same 100 bytes.
+        let mut data = [b"II".as_slice(), &[42, 0], &[8, 0, 0, 0], &[20, 0]].concat();
+        for tag in 0..20u16 {
+            data.extend_from_slice(&[&tag.to_le_bytes()[..], &[7, 0], &[100, 0, 0, 0], &[0, 1, 0, 0]].concat());
+        }
+        data.resize(0x100 + 100, 0);
+        assert!(matches!(Exif::from_data(&data), Err(PngyinxError::Exif(TooLarge))));
+    }
+
+    /// IFD0 with Make, a field of the unknown type 99 and a pointer to a GPS IFD holding the
+    /// latitude, then IFD1 describing a thumbnail.
+    fn exif_with_thumbnail() -> Vec<u8> {
+        [
+            b"II".as_slice(), &[42, 0], &[8, 0, 0, 0],
+            &[3, 0],
+            &[0x0f, 0x01, 2, 0, 4, 0, 0, 0, b'A', b'B', b'C', 0],
+            &[0x00, 0xc0, 99, 0, 1, 0, 0, 0, 1, 2, 3, 4],
+            &[0x25, 0x88, 4, 0, 1, 0, 0, 0, 50, 0, 0, 0],
+            &[92, 0, 0, 0],
+            // GPS IFD at 50, its latitude at 68.
+            &[1, 0],
+            &[0x02, 0x00, 5, 0, 3, 0, 0, 0, 68, 0, 0, 0],
+            &[0, 0, 0, 0],
+            &[33, 0, 0, 0, 1, 0, 0, 0, 30, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0],
+            // IFD1 at 92, the thumbnail at 110.
+            &[1, 0],
+            &[0x03, 0x01, 3, 0, 1, 0, 0, 0, 6, 0, 0, 0],
+            &[0, 0, 0, 0],
+            b"thumbnail",
+        ].concat()
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let data = exif_with_thumbnail();
+        let mut exif = Exif::from_data(&data).unwrap();
+        assert_eq!(exif.make(), Some("ABC"));
+        assert_eq!(exif.unsupported(), [Unsupported::Field(Ifd::Primary, 0xc000, 99), Unsupported::Thumbnail]);
+        assert!(matches!(exif.to_data(), Err(PngyinxError::Exif(WouldLose(_)))));
+        exif.drop_unsupported();
+        assert_eq!(Exif::from_data(&exif.to_data().unwrap()).unwrap().entries(), exif.entries());
+    }
+
+    #[test]
+    fn test_erase_gps() {
+        let original = exif_with_thumbnail();
+        let mut data = original.clone();
+        assert_eq!(erase_gps(&mut data).unwrap(), 1);
+        assert_eq!(data.len(), original.len());
+        let exif = Exif::from_data(&data).unwrap();
+        assert_eq!(exif.make(), Some("ABC"));
+        assert_eq!(exif.get(Ifd::Gps, GPS_LATITUDE), None);
+        assert_eq!(exif.unsupported(), [Unsupported::Field(Ifd::Primary, 0xc000, 99), Unsupported::Thumbnail]);
+        // The unknown field and IFD1 are untouched, the GPS IFD and its values are gone.
+        assert_eq!(data[22..34], original[22..34]);
+        assert_eq!(data[92..], original[92..]);
+        assert!(data[50..92].iter().all(|&byte| byte == 0));
+        assert_eq!(erase_gps(&mut data).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_non_utf8_ascii() {
+        // Make = "\xa9 Me", Latin-1 rather than UTF-8.
+        let data = [
+            b"II".as_slice(), &[42, 0], &[8, 0, 0, 0],
+            &[1, 0],
+            &[0x0f, 0x01, 2, 0, 6, 0, 0, 0, 26, 0, 0, 0],
+            &[0, 0, 0, 0],
+            b"\xa9 Me\0\0",
+        ].concat();
+        let mut exif = Exif::from_data(&data).unwrap();
+        assert_eq!(exif.unsupported(), [Unsupported::Ascii(Ifd::Primary, MAKE)]);
+        assert!(matches!(exif.to_data(), Err(PngyinxError::Exif(WouldLose(_)))));
+        exif.drop_unsupported();
+        assert_eq!(Exif::from_data(&exif.to_data().unwrap()).unwrap().make(), Some("\u{fffd} Me"));
+    }
+
+    #[test]
+    fn test_erase_gps_malformed_pointer() {
+        let mut data = exif_with_thumbnail();
+        // The GPS pointer claims two offsets.
+        data[38] = 2;
+        assert!(Exif::from_data(&data).unwrap().unsupported().contains(&Unsupported::MalformedPointer(Ifd::Primary, 0x8825)));
+        let original = data.clone();
+        assert!(matches!(erase_gps(&mut data), Err(PngyinxError::Exif(MalformedPointer(0x8825)))));
+        assert_eq!(data, original);
+    }
+
+    #[test]
+    fn test_erase_gps_shared_value() {
+        let mut original = exif_with_thumbnail();
+        // The unknown field becomes three rationals sharing the latitude.
+        original[22..34].copy_from_slice(&[0x00, 0xc0, 5, 0, 3, 0, 0, 0, 68, 0, 0, 0]);
+        let mut data = original.clone();
+        assert_eq!(erase_gps(&mut data).unwrap(), 1);
+        let exif = Exif::from_data(&data).unwrap();
+        assert_eq!(exif.get(Ifd::Primary, 0xc000), Some(&Value::Rational(vec![(33, 1), (30, 1), (0, 1)])));
+        assert_eq!(exif.get(Ifd::Gps, GPS_LATITUDE), None);
+        assert_eq!(data[68..92], original[68..92]);
+        assert!(data[50..68].iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn test_png_exif() {
+        let mut png = Png::try_from(PNG_FILE).unwrap();
+        assert_eq!(png.exif().unwrap(), None);
+        png.set_exif(&exif(ByteOrder::BigEndian)).unwrap();
+        assert_eq!(png.exif().unwrap().unwrap().model(), None);
+        assert_eq!(png.exif().unwrap().unwrap().make(), Some("Pngyinx"));
+        assert!(png.validate().is_empty());
+    }
+}
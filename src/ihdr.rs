@@ -0,0 +1,293 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::ihdr::IhdrError::{DimensionTooLarge, InvalidBitDepth, NotIhdr, UnknownColorType, UnknownCompressionMethod, UnknownFilterMethod, UnknownInterlaceMethod, WrongLength, ZeroDimension};
+use crate::Error;
+use crate::Result;
+
+pub const IHDR_CHUNK_TYPE: &str = "IHDR";
+const LENGTH: usize = 13;
+
+/// How pixels are made of samples, as stored in IHDR.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "snake_case"))]
+pub enum ColorType {
+    Grayscale = 0,
+    Truecolor = 2,
+    Indexed = 3,
+    GrayscaleAlpha = 4,
+    TruecolorAlpha = 6,
+}
+
+impl TryFrom<u8> for ColorType {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(ColorType::Grayscale),
+            2 => Ok(ColorType::Truecolor),
+            3 => Ok(ColorType::Indexed),
+            4 => Ok(ColorType::GrayscaleAlpha),
+            6 => Ok(ColorType::TruecolorAlpha),
+            _ => Err(UnknownColorType(value).into()),
+        }
+    }
+}
+
+impl Display for ColorType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColorType::Grayscale => write!(f, "grayscale"),
+            ColorType::Truecolor => write!(f, "truecolor"),
+            ColorType::Indexed => write!(f, "indexed"),
+            ColorType::GrayscaleAlpha => write!(f, "grayscale with alpha"),
+            ColorType::TruecolorAlpha => write!(f, "truecolor with alpha"),
+        }
+    }
+}
+
+impl ColorType {
+    /// The bit depths the specification allows with this color type.
+    pub fn allowed_bit_depths(&self) -> &'static [u8] {
+        match self {
+            ColorType::Grayscale => &[1, 2, 4, 8, 16],
+            ColorType::Indexed => &[1, 2, 4, 8],
+            ColorType::Truecolor | ColorType::GrayscaleAlpha | ColorType::TruecolorAlpha => &[8, 16],
+        }
+    }
+
+    /// The number of samples per pixel.
+    pub fn channels(&self) -> u8 {
+        match self {
+            ColorType::Grayscale | ColorType::Indexed => 1,
+            ColorType::GrayscaleAlpha => 2,
+            ColorType::Truecolor => 3,
+            ColorType::TruecolorAlpha => 4,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "snake_case"))]
+pub enum InterlaceMethod {
+    None = 0,
+    Adam7 = 1,
+}
+
+/// The image header, the first chunk of every png.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Ihdr {
+    pub width: u32,
+    pub height: u32,
+    pub bit_depth: u8,
+    pub color_type: ColorType,
+    pub compression_method: u8,
+    pub filter_method: u8,
+    pub interlace_method: InterlaceMethod,
+}
+
+impl TryFrom<&Chunk> for Ihdr {
+    type Error = Error;
+
+    fn try_from(value: &Chunk) -> Result<Self> {
+        if value.chunk_type().bytes() != IHDR_CHUNK_TYPE.as_bytes() {
+            return Err(NotIhdr(*value.chunk_type()).into());
+        }
+        Ihdr::from_data(value.data())
+    }
+}
+
+impl Display for Ihdr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}, {}-bit {}", self.width, self.height, self.bit_depth, self.color_type)?;
+        match self.interlace_method {
+            InterlaceMethod::None => write!(f, ", not interlaced"),
+            InterlaceMethod::Adam7 => write!(f, ", Adam7 interlaced"),
+        }
+    }
+}
+
+impl Ihdr {
+    /// Parses and checks the data of an IHDR chunk.
+    pub fn from_data(data: &[u8]) -> Result<Ihdr> {
+        if data.len() != LENGTH {
+            return Err(WrongLength(data.len()).into());
+        }
+        let width = u32::from_be_bytes(data[0..4].try_into().unwrap());
+        let height = u32::from_be_bytes(data[4..8].try_into().unwrap());
+        let color_type = ColorType::try_from(data[9])?;
+        let interlace_method = match data[12] {
+            0 => InterlaceMethod::None,
+            1 => InterlaceMethod::Adam7,
+            method => return Err(UnknownInterlaceMethod(method).into()),
+        };
+        let ihdr = Ihdr {
+            width,
+            height,
+            bit_depth: data[8],
+            color_type,
+            compression_method: data[10],
+            filter_method: data[11],
+            interlace_method,
+        };
+        ihdr.check()?;
+        Ok(ihdr)
+    }
+
+    /// Checks the fields against the specification: dimensions between 1 and 2^31-1, a bit depth
+    /// allowed for the color type, and the only compression and filter methods defined.
+    pub fn check(&self) -> Result<()> {
+        for dimension in [self.width, self.height] {
+            if dimension == 0 {
+                return Err(ZeroDimension.into());
+            }
+            if dimension > Chunk::MAX_LENGTH {
+                return Err(DimensionTooLarge(dimension).into());
+            }
+        }
+        if !self.color_type.allowed_bit_depths().contains(&self.bit_depth) {
+            return Err(InvalidBitDepth(self.bit_depth, self.color_type).into());
+        }
+        if self.compression_method != 0 {
+            return Err(UnknownCompressionMethod(self.compression_method).into());
+        }
+        if self.filter_method != 0 {
+            return Err(UnknownFilterMethod(self.filter_method).into());
+        }
+        Ok(())
+    }
+
+    pub fn to_data(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(LENGTH);
+        data.extend_from_slice(&self.width.to_be_bytes());
+        data.extend_from_slice(&self.height.to_be_bytes());
+        data.push(self.bit_depth);
+        data.push(self.color_type as u8);
+        data.push(self.compression_method);
+        data.push(self.filter_method);
+        data.push(self.interlace_method as u8);
+        data
+    }
+
+    /// Builds the IHDR chunk, CRC included, after checking the fields.
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        self.check()?;
+        Ok(Chunk::new(ChunkType::from_str(IHDR_CHUNK_TYPE)?, self.to_data()))
+    }
+
+    /// The number of bits in one pixel.
+    pub fn bits_per_pixel(&self) -> usize {
+        self.bit_depth as usize * self.color_type.channels() as usize
+    }
+}
+
+#[derive(Debug)]
+pub enum IhdrError {
+    NotIhdr(ChunkType),
+    WrongLength(usize),
+    ZeroDimension,
+    DimensionTooLarge(u32),
+    UnknownColorType(u8),
+    InvalidBitDepth(u8, ColorType),
+    UnknownCompressionMethod(u8),
+    UnknownFilterMethod(u8),
+    UnknownInterlaceMethod(u8),
+}
+
+impl Display for IhdrError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NotIhdr(chunk_type) => write!(f, "Expected an IHDR chunk but found {}", chunk_type),
+            WrongLength(length) => write!(f, "IHDR must be 13 bytes long, not {}", length),
+            ZeroDimension => write!(f, "The width and height must not be zero"),
+            DimensionTooLarge(dimension) => write!(f, "The dimension {} exceeds the maximum of 2^31-1", dimension),
+            UnknownColorType(color_type) => write!(f, "Unknown color type: {}", color_type),
+            InvalidBitDepth(bit_depth, color_type) => write!(f, "A bit depth of {} isn't allowed for {} images", bit_depth, color_type),
+            UnknownCompressionMethod(method) => write!(f, "Unknown compression method: {}", method),
+            UnknownFilterMethod(method) => write!(f, "Unknown filter method: {}", method),
+            UnknownInterlaceMethod(method) => write!(f, "Unknown interlace method: {}", method),
+        }
+    }
+}
+
+impl IhdrError {
+    /// A stable identifier for the error, for machine-readable output.
+    pub fn code(&self) -> &'static str {
+        match self {
+            NotIhdr(_) => "not_ihdr",
+            WrongLength(_) => "wrong_length",
+            ZeroDimension => "zero_dimension",
+            DimensionTooLarge(_) => "dimension_too_large",
+            UnknownColorType(_) => "unknown_color_type",
+            InvalidBitDepth(_, _) => "invalid_bit_depth",
+            UnknownCompressionMethod(_) => "unknown_compression_method",
+            UnknownFilterMethod(_) => "unknown_filter_method",
+            UnknownInterlaceMethod(_) => "unknown_interlace_method",
+        }
+    }
+}
+
+impl std::error::Error for IhdrError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::Png;
+    use crate::PngyinxError;
+
+    const PNG_FILE: &[u8] = include_bytes!("../pictures/dice.png");
+
+    fn ihdr() -> Ihdr {
+        Ihdr {
+            width: 640,
+            height: 480,
+            bit_depth: 8,
+            color_type: ColorType::TruecolorAlpha,
+            compression_method: 0,
+            filter_method: 0,
+            interlace_method: InterlaceMethod::None,
+        }
+    }
+
+    #[test]
+    fn test_ihdr_from_png() {
+        let png = Png::try_from(PNG_FILE).unwrap();
+        let ihdr = png.ihdr().unwrap();
+        assert!(ihdr.width > 0 && ihdr.height > 0);
+        assert_eq!(ihdr.to_chunk().unwrap(), *png.chunk_by_type(IHDR_CHUNK_TYPE).unwrap());
+    }
+
+    #[test]
+    fn test_ihdr_round_trip() {
+        let chunk = ihdr().to_chunk().unwrap();
+        assert_eq!(chunk.length(), 13);
+        let bytes = chunk.as_bytes();
+        let chunk = Chunk::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(Ihdr::try_from(&chunk).unwrap(), ihdr());
+        assert_eq!(ihdr().to_string(), "640x480, 8-bit truecolor with alpha, not interlaced");
+        assert_eq!(ihdr().bits_per_pixel(), 32);
+    }
+
+    #[test]
+    fn test_invalid_bit_depth() {
+        let ihdr = Ihdr { bit_depth: 4, ..ihdr() };
+        assert!(matches!(ihdr.to_chunk(), Err(PngyinxError::Ihdr(InvalidBitDepth(4, ColorType::TruecolorAlpha)))));
+        let ihdr = Ihdr { bit_depth: 16, color_type: ColorType::Indexed, ..ihdr };
+        assert!(matches!(ihdr.check(), Err(PngyinxError::Ihdr(InvalidBitDepth(16, ColorType::Indexed)))));
+    }
+
+    #[test]
+    fn test_invalid_fields() {
+        let mut data = ihdr().to_data();
+        assert!(matches!(Ihdr::from_data(&data[..12]), Err(PngyinxError::Ihdr(WrongLength(12)))));
+        data[9] = 5;
+        assert!(matches!(Ihdr::from_data(&data), Err(PngyinxError::Ihdr(UnknownColorType(5)))));
+        data[9] = 6;
+        data[12] = 2;
+        assert!(matches!(Ihdr::from_data(&data), Err(PngyinxError::Ihdr(UnknownInterlaceMethod(2)))));
+        assert!(matches!(Ihdr { width: 0, ..ihdr() }.check(), Err(PngyinxError::Ihdr(ZeroDimension))));
+        assert!(matches!(Ihdr { filter_method: 1, ..ihdr() }.check(), Err(PngyinxError::Ihdr(UnknownFilterMethod(1)))));
+    }
+}
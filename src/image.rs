@@ -0,0 +1,391 @@
+use std::fmt::{Display, Formatter};
+use std::io::Read;
+use flate2::read::ZlibDecoder;
+use crate::ihdr::{ColorType, Ihdr, InterlaceMethod};
+use crate::image::ImageError::{DecompressionFailed, TooLarge, UnknownFilterType, WrongDataLength};
+use crate::Result;
+
+/// The Adam7 passes, as (first column, first row, column step, row step).
+const ADAM7_PASSES: [(u32, u32, u32, u32); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+/// The pixels of an image, unfiltered and deinterlaced. Rows are packed as in the png: samples
+/// of less than 8 bits share bytes from the most significant bit, 16-bit samples are big-endian,
+/// and each row starts on a new byte.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub color_type: ColorType,
+    pub bit_depth: u8,
+    data: Vec<u8>,
+}
+
+impl Image {
+    /// Decodes the concatenated data of the IDAT chunks of the image described by `ihdr`.
+    pub fn decode(ihdr: &Ihdr, idat: &[u8]) -> Result<Image> {
+        ihdr.check()?;
+        let bits_per_pixel = ihdr.bits_per_pixel();
+        let passes: Vec<(u32, u32, u32, u32)> = match ihdr.interlace_method {
+            InterlaceMethod::None => vec![(0, 0, 1, 1)],
+            InterlaceMethod::Adam7 => ADAM7_PASSES.to_vec(),
+        };
+        let sizes: Vec<(usize, usize)> = passes.iter()
+            .map(|&(x, y, dx, dy)| (pass_length(ihdr.width, x, dx), pass_length(ihdr.height, y, dy)))
+            .collect();
+        let expected = sizes.iter()
+            .filter(|(width, height)| *width > 0 && *height > 0)
+            .map(|&(width, height)| (1 + row_length(width, bits_per_pixel)?).checked_mul(height))
+            .try_fold(0usize, |total, length| total.checked_add(length?))
+            .ok_or(TooLarge)?;
+        let data = inflate(idat, expected)?;
+
+        let mut image = Image {
+            width: ihdr.width,
+            height: ihdr.height,
+            color_type: ihdr.color_type,
+            bit_depth: ihdr.bit_depth,
+            data: Vec::new(),
+        };
+        if ihdr.interlace_method == InterlaceMethod::None {
+            image.data = unfilter(&data, ihdr.width as usize, ihdr.height as usize, bits_per_pixel)?;
+            return Ok(image);
+        }
+        let row_length = image.row_length();
+        image.data = vec![0; row_length * ihdr.height as usize];
+        let mut offset = 0;
+        for (&(x0, y0, dx, dy), &(width, height)) in passes.iter().zip(&sizes) {
+            if width == 0 || height == 0 {
+                continue;
+            }
+            let length = (1 + row_length_unchecked(width, bits_per_pixel)) * height;
+            let pass = unfilter(&data[offset..offset + length], width, height, bits_per_pixel)?;
+            offset += length;
+            let pass_row_length = row_length_unchecked(width, bits_per_pixel);
+            for y in 0..height {
+                let row = &pass[y * pass_row_length..(y + 1) * pass_row_length];
+                let target_y = y0 as usize + y * dy as usize;
+                let target = &mut image.data[target_y * row_length..(target_y + 1) * row_length];
+                for x in 0..width {
+                    copy_pixel(row, x, target, x0 as usize + x * dx as usize, bits_per_pixel);
+                }
+            }
+        }
+        Ok(image)
+    }
+
+    /// The rows, one after the other, without filter bytes.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+
+    /// The number of bytes in a row.
+    pub fn row_length(&self) -> usize {
+        row_length_unchecked(self.width as usize, self.bits_per_pixel())
+    }
+
+    /// The bytes of row `y`, or `None` if it is past the bottom of the image.
+    pub fn row(&self, y: u32) -> Option<&[u8]> {
+        if y >= self.height {
+            return None;
+        }
+        let length = self.row_length();
+        Some(&self.data[y as usize * length..(y as usize + 1) * length])
+    }
+
+    pub fn bits_per_pixel(&self) -> usize {
+        self.bit_depth as usize * self.color_type.channels() as usize
+    }
+
+    /// The samples of the pixel at (`x`, `y`), one per channel, or `None` if it is outside the
+    /// image. Indexed images give the palette index.
+    pub fn samples(&self, x: u32, y: u32) -> Option<Vec<u16>> {
+        if x >= self.width {
+            return None;
+        }
+        let row = self.row(y)?;
+        let channels = self.color_type.channels() as usize;
+        let samples = (0..channels)
+            .map(|channel| {
+                let index = x as usize * channels + channel;
+                match self.bit_depth {
+                    16 => u16::from_be_bytes([row[index * 2], row[index * 2 + 1]]),
+                    8 => row[index] as u16,
+                    depth => read_bits(row, index * depth as usize, depth as usize) as u16,
+                }
+            })
+            .collect();
+        Some(samples)
+    }
+}
+
+/// The number of pixels of a pass along one dimension.
+fn pass_length(length: u32, start: u32, step: u32) -> usize {
+    if length <= start { 0 } else { ((length - start).div_ceil(step)) as usize }
+}
+
+fn row_length(width: usize, bits_per_pixel: usize) -> Option<usize> {
+    Some(width.checked_mul(bits_per_pixel)?.div_ceil(8))
+}
+
+fn row_length_unchecked(width: usize, bits_per_pixel: usize) -> usize {
+    (width * bits_per_pixel).div_ceil(8)
+}
+
+/// Inflates exactly `expected` bytes, reading no more than that so that a lying stream can't
+/// exhaust memory.
+fn inflate(idat: &[u8], expected: usize) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    ZlibDecoder::new(idat)
+        .take(expected as u64 + 1)
+        .read_to_end(&mut data)
+        .map_err(|_| DecompressionFailed)?;
+    if data.len() != expected {
+        return Err(WrongDataLength(expected, data.len()).into());
+    }
+    Ok(data)
+}
+
+/// Reverses the filter of each row of `data`, each preceded by its filter type.
+fn unfilter(data: &[u8], width: usize, height: usize, bits_per_pixel: usize) -> Result<Vec<u8>> {
+    let length = row_length_unchecked(width, bits_per_pixel);
+    // The filters work on bytes: the distance to the same byte of the previous pixel, at least 1.
+    let distance = bits_per_pixel.div_ceil(8);
+    let mut pixels = vec![0; length * height];
+    for y in 0..height {
+        let filtered = &data[y * (length + 1)..(y + 1) * (length + 1)];
+        let (previous, current) = pixels.split_at_mut(y * length);
+        let previous = if y == 0 { None } else { Some(&previous[(y - 1) * length..]) };
+        let current = &mut current[..length];
+        current.copy_from_slice(&filtered[1..]);
+        for i in 0..length {
+            let left = if i >= distance { current[i - distance] } else { 0 };
+            let up = previous.map_or(0, |previous| previous[i]);
+            let up_left = if i >= distance { previous.map_or(0, |previous| previous[i - distance]) } else { 0 };
+            let prediction = match filtered[0] {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((left as u16 + up as u16) / 2) as u8,
+                4 => paeth(left, up, up_left),
+                filter_type => return Err(UnknownFilterType(filter_type, y).into()),
+            };
+            current[i] = current[i].wrapping_add(prediction);
+        }
+    }
+    Ok(pixels)
+}
+
+fn paeth(left: u8, up: u8, up_left: u8) -> u8 {
+    let estimate = left as i16 + up as i16 - up_left as i16;
+    let distance_left = (estimate - left as i16).abs();
+    let distance_up = (estimate - up as i16).abs();
+    let distance_up_left = (estimate - up_left as i16).abs();
+    if distance_left <= distance_up && distance_left <= distance_up_left {
+        left
+    } else if distance_up <= distance_up_left {
+        up
+    } else {
+        up_left
+    }
+}
+
+fn read_bits(row: &[u8], bit: usize, count: usize) -> u8 {
+    (row[bit / 8] >> (8 - count - bit % 8)) & ((1 << count) - 1) as u8
+}
+
+/// Copies pixel `x` of `source` to pixel `target_x` of `target`.
+fn copy_pixel(source: &[u8], x: usize, target: &mut [u8], target_x: usize, bits_per_pixel: usize) {
+    if bits_per_pixel >= 8 {
+        let bytes = bits_per_pixel / 8;
+        target[target_x * bytes..(target_x + 1) * bytes].copy_from_slice(&source[x * bytes..(x + 1) * bytes]);
+        return;
+    }
+    let value = read_bits(source, x * bits_per_pixel, bits_per_pixel);
+    let bit = target_x * bits_per_pixel;
+    target[bit / 8] |= value << (8 - bits_per_pixel - bit % 8);
+}
+
+#[derive(Debug)]
+pub enum ImageError {
+    DecompressionFailed,
+    WrongDataLength(usize, usize),
+    UnknownFilterType(u8, usize),
+    TooLarge,
+}
+
+impl Display for ImageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecompressionFailed => write!(f, "The image data couldn't be decompressed"),
+            WrongDataLength(expected, actual) => write!(f, "Expected {} bytes of image data but found {}", expected, actual),
+            UnknownFilterType(filter_type, row) => write!(f, "Unknown filter type {} on row {}", filter_type, row),
+            TooLarge => write!(f, "The image is too large to be decoded"),
+        }
+    }
+}
+
+impl ImageError {
+    /// A stable identifier for the error, for machine-readable output.
+    pub fn code(&self) -> &'static str {
+        match self {
+            DecompressionFailed => "decompression_failed",
+            WrongDataLength(_, _) => "wrong_data_length",
+            UnknownFilterType(_, _) => "unknown_filter_type",
+            TooLarge => "too_large",
+        }
+    }
+}
+
+impl std::error::Error for ImageError {}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use super::*;
+    use crate::png::Png;
+    use crate::PngyinxError;
+
+    const PNG_FILE: &[u8] = include_bytes!("../pictures/dice.png");
+
+    fn ihdr(width: u32, height: u32, color_type: ColorType, bit_depth: u8, interlace_method: InterlaceMethod) -> Ihdr {
+        Ihdr { width, height, bit_depth, color_type, compression_method: 0, filter_method: 0, interlace_method }
+    }
+
+    fn deflate(data: &[u8]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    /// Filters each row of `pixels` with the next filter type in turn.
+    fn filter(pixels: &[u8], length: usize, distance: usize) -> Vec<u8> {
+        let mut data = Vec::new();
+        for (y, row) in pixels.chunks(length).enumerate() {
+            let filter_type = (y % 5) as u8;
+            data.push(filter_type);
+            for i in 0..length {
+                let left = if i >= distance { row[i - distance] } else { 0 };
+                let up = if y > 0 { pixels[(y - 1) * length + i] } else { 0 };
+                let up_left = if y > 0 && i >= distance { pixels[(y - 1) * length + i - distance] } else { 0 };
+                let prediction = match filter_type {
+                    0 => 0,
+                    1 => left,
+                    2 => up,
+                    3 => ((left as u16 + up as u16) / 2) as u8,
+                    _ => paeth(left, up, up_left),
+                };
+                data.push(row[i].wrapping_sub(prediction));
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn test_decode_dice() {
+        let png = Png::try_from(PNG_FILE).unwrap();
+        let image = png.decode_pixels().unwrap();
+        assert_eq!((image.width, image.height, image.bit_depth), (50, 50, 8));
+        assert_eq!(image.color_type, ColorType::TruecolorAlpha);
+        assert_eq!(image.data().len(), 50 * 50 * 4);
+        assert_eq!(image.samples(0, 0).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_every_filter() {
+        for (color_type, bit_depth) in [(ColorType::Truecolor, 8), (ColorType::GrayscaleAlpha, 16), (ColorType::Grayscale, 2)] {
+            let ihdr = ihdr(7, 10, color_type, bit_depth, InterlaceMethod::None);
+            let length = row_length_unchecked(7, ihdr.bits_per_pixel());
+            let pixels: Vec<u8> = (0..length * 10).map(|i| (i * 37 % 251) as u8).collect();
+            let idat = deflate(&filter(&pixels, length, ihdr.bits_per_pixel().div_ceil(8)));
+            let image = Image::decode(&ihdr, &idat).unwrap();
+            assert_eq!(image.data(), pixels.as_slice());
+        }
+    }
+
+    #[test]
+    fn test_samples() {
+        let gray = ihdr(5, 1, ColorType::Grayscale, 1, InterlaceMethod::None);
+        let image = Image::decode(&gray, &deflate(&[0, 0b1011_0000])).unwrap();
+        let samples: Vec<u16> = (0..5).map(|x| image.samples(x, 0).unwrap()[0]).collect();
+        assert_eq!(samples, vec![1, 0, 1, 1, 0]);
+
+        let truecolor = ihdr(1, 1, ColorType::Truecolor, 16, InterlaceMethod::None);
+        let image = Image::decode(&truecolor, &deflate(&[0, 1, 2, 3, 4, 5, 6])).unwrap();
+        assert_eq!(image.samples(0, 0), Some(vec![0x0102, 0x0304, 0x0506]));
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let gray = ihdr(3, 2, ColorType::Grayscale, 8, InterlaceMethod::None);
+        let image = Image::decode(&gray, &deflate(&[0, 1, 2, 3, 0, 4, 5, 6])).unwrap();
+        assert_eq!(image.row(1), Some([4, 5, 6].as_slice()));
+        assert_eq!(image.row(2), None);
+        assert_eq!(image.samples(2, 1), Some(vec![6]));
+        assert_eq!(image.samples(3, 0), None);
+        assert_eq!(image.samples(0, 2), None);
+    }
+
+    #[test]
+    fn test_adam7() {
+        for bit_depth in [1, 4, 8, 16] {
+            let (width, height) = (11, 9);
+            let plain = ihdr(width, height, ColorType::Grayscale, bit_depth, InterlaceMethod::None);
+            let max = (1u32 << bit_depth) - 1;
+            let value = |x: u32, y: u32| (x * 7 + y * 13) % (max + 1);
+            let bits = bit_depth as usize;
+            // Unfiltered rows holding the pixels at these columns and lines.
+            let rows = |columns: Vec<u32>, lines: Vec<u32>| {
+                let mut data = Vec::new();
+                for &y in &lines {
+                    let mut row = vec![0u8; row_length_unchecked(columns.len(), bits)];
+                    for (i, &x) in columns.iter().enumerate() {
+                        let sample = value(x, y);
+                        if bits == 16 {
+                            row[i * 2..i * 2 + 2].copy_from_slice(&(sample as u16).to_be_bytes());
+                        } else {
+                            row[i * bits / 8] |= (sample as u8) << (8 - bits - i * bits % 8);
+                        }
+                    }
+                    data.push(0);
+                    data.extend(row);
+                }
+                data
+            };
+            let mut interlaced = Vec::new();
+            for (x0, y0, dx, dy) in ADAM7_PASSES {
+                let columns: Vec<u32> = (x0..width).step_by(dx as usize).collect();
+                let lines: Vec<u32> = (y0..height).step_by(dy as usize).collect();
+                if !columns.is_empty() {
+                    interlaced.extend(rows(columns, lines));
+                }
+            }
+            let expected = Image::decode(&plain, &deflate(&rows((0..width).collect(), (0..height).collect()))).unwrap();
+            let adam7 = ihdr(width, height, ColorType::Grayscale, bit_depth, InterlaceMethod::Adam7);
+            let image = Image::decode(&adam7, &deflate(&interlaced)).unwrap();
+            assert_eq!(image.data(), expected.data());
+            assert_eq!(image.samples(10, 8), Some(vec![value(10, 8) as u16]));
+        }
+    }
+
+    #[test]
+    fn test_invalid_data() {
+        let gray = ihdr(2, 2, ColorType::Grayscale, 8, InterlaceMethod::None);
+        assert!(matches!(Image::decode(&gray, &deflate(&[0, 1, 2, 5, 3, 4])), Err(PngyinxError::Image(UnknownFilterType(5, 1)))));
+        assert!(matches!(Image::decode(&gray, &deflate(&[0, 1, 2])), Err(PngyinxError::Image(WrongDataLength(6, 3)))));
+        assert!(matches!(Image::decode(&gray, b"not zlib"), Err(PngyinxError::Image(DecompressionFailed))));
+    }
+}
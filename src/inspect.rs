@@ -0,0 +1,156 @@
+use crate::chunk_ref::ChunkRef;
+use crate::chunk_type::ChunkType;
+use crate::ihdr::{Ihdr, IHDR_CHUNK_TYPE};
+use crate::png::Png;
+use crate::png_ref::PngRef;
+use crate::signature::SIGNATURE_CHUNK_TYPE;
+use crate::time::{ImageTime, TIME_CHUNK_TYPE};
+use crate::Error;
+use crate::Result;
+
+/// The chunk types defined by the png specification and its extensions.
+pub const KNOWN_CHUNKS: [&str; 25] = [
+    "IHDR", "PLTE", "IDAT", "IEND", "acTL", "bKGD", "cHRM", "cICP", "cLLI", "eXIf", "fcTL",
+    "fdAT", "gAMA", "hIST", "iCCP", "iTXt", "mDCV", "pHYs", "sBIT", "sPLT", "sRGB", "tEXt",
+    "tIME", "tRNS", "zTXt",
+];
+
+/// What `inspect` finds out about one chunk.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ChunkInfo {
+    pub index: usize,
+    pub offset: usize,
+    pub chunk_type: ChunkType,
+    pub length: u32,
+    pub stored_crc: u32,
+    pub computed_crc: u32,
+}
+
+impl ChunkInfo {
+    pub fn crc_matches(&self) -> bool {
+        self.stored_crc == self.computed_crc
+    }
+
+    /// Whether the type is neither in the specification nor one of ours.
+    pub fn is_unknown(&self) -> bool {
+        let chunk_type = self.chunk_type.to_string();
+        !KNOWN_CHUNKS.contains(&chunk_type.as_str()) && chunk_type != SIGNATURE_CHUNK_TYPE
+    }
+}
+
+/// The chunks of a file, read as far as possible.
+#[derive(Debug)]
+pub struct Report {
+    pub chunks: Vec<ChunkInfo>,
+    /// The size of the file, signature included.
+    pub size: usize,
+    /// Why the chunks stop early, if they do. Wrong CRCs don't stop them.
+    pub error: Option<Error>,
+    /// The parsed header, if there is an IHDR chunk.
+    pub ihdr: Option<Result<Ihdr>>,
+    /// The time of the last modification, if there is a tIME chunk.
+    pub time: Option<Result<ImageTime>>,
+}
+
+impl Report {
+    /// The unknown chunk types, each listed once in order of appearance.
+    pub fn unknown_types(&self) -> Vec<ChunkType> {
+        let mut unknown_types = Vec::new();
+        for chunk in self.chunks.iter().filter(|chunk| chunk.is_unknown()) {
+            if !unknown_types.contains(&chunk.chunk_type) {
+                unknown_types.push(chunk.chunk_type);
+            }
+        }
+        unknown_types
+    }
+}
+
+/// Lists the chunks of `bytes` without requiring their CRCs to be right.
+/// Only a missing png signature is an error, other damage ends the list and is kept in the report.
+pub fn inspect(bytes: &[u8]) -> Result<Report> {
+    PngRef::try_from(bytes)?;
+    let mut chunks = Vec::new();
+    let mut offset = Png::STANDARD_HEADER.len();
+    let mut error = None;
+    let mut ihdr = None;
+    let mut time = None;
+    while offset < bytes.len() {
+        match ChunkRef::from_bytes_at_unchecked(&bytes[offset..], offset) {
+            Ok(chunk) => {
+                if ihdr.is_none() && chunk.chunk_type().bytes() == IHDR_CHUNK_TYPE.as_bytes() {
+                    ihdr = Some(Ihdr::from_data(chunk.data()));
+                }
+                if time.is_none() && chunk.chunk_type().bytes() == TIME_CHUNK_TYPE.as_bytes() {
+                    time = Some(ImageTime::from_data(chunk.data()));
+                }
+                chunks.push(ChunkInfo {
+                    index: chunks.len(),
+                    offset,
+                    chunk_type: *chunk.chunk_type(),
+                    length: chunk.length(),
+                    stored_crc: chunk.crc(),
+                    computed_crc: chunk.computed_crc(),
+                });
+                offset += chunk.as_bytes().len();
+            },
+            Err(e) => {
+                error = Some(e);
+                break;
+            },
+        }
+    }
+    Ok(Report { chunks, size: bytes.len(), error, ihdr, time })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::ChunkError;
+    use crate::PngyinxError;
+
+    const PNG_FILE: &[u8] = include_bytes!("../pictures/dice.png");
+
+    #[test]
+    fn test_inspect() {
+        let report = inspect(PNG_FILE).unwrap();
+        let types: Vec<String> = report.chunks.iter().map(|chunk| chunk.chunk_type.to_string()).collect();
+        assert_eq!(types, vec!["IHDR", "sRGB", "gAMA", "pHYs", "IDAT", "RuSt", "IEND"]);
+        assert_eq!(report.chunks[0].offset, 8);
+        assert_eq!(report.chunks[0].length, 13);
+        assert_eq!(report.chunks[1].offset, 33);
+        assert!(report.chunks.iter().all(ChunkInfo::crc_matches));
+        assert_eq!(report.unknown_types(), vec![ChunkType::try_from(*b"RuSt").unwrap()]);
+        assert_eq!(report.size, PNG_FILE.len());
+        assert!(report.error.is_none());
+        assert_eq!(report.ihdr.unwrap().unwrap(), Png::try_from(PNG_FILE).unwrap().ihdr().unwrap());
+        assert!(report.time.is_none());
+    }
+
+    #[test]
+    fn test_inspect_time() {
+        let mut png = Png::try_from(PNG_FILE).unwrap();
+        let time = ImageTime::new(2024, 6, 1, 12, 0, 0).unwrap();
+        png.set_time(&time).unwrap();
+        let report = inspect(&png.as_bytes()).unwrap();
+        assert_eq!(report.time.unwrap().unwrap(), time);
+    }
+
+    #[test]
+    fn test_inspect_wrong_crc() {
+        let mut bytes = PNG_FILE.to_vec();
+        bytes[41] ^= 1;
+        let report = inspect(&bytes).unwrap();
+        assert_eq!(report.chunks.len(), 7);
+        assert!(!report.chunks[1].crc_matches());
+        assert!(report.chunks.iter().filter(|chunk| chunk.index != 1).all(ChunkInfo::crc_matches));
+    }
+
+    #[test]
+    fn test_inspect_truncated() {
+        let report = inspect(&PNG_FILE[..41]).unwrap();
+        assert_eq!(report.chunks.len(), 1);
+        assert!(matches!(report.error, Some(PngyinxError::Chunk(ChunkError::DataPastEnd(33, _, _)))));
+        assert!(inspect(b"not a png").is_err());
+    }
+}
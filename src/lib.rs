@@ -0,0 +1,43 @@
+pub mod chunk;
+pub mod chunk_ref;
+pub mod chunk_type;
+pub mod color;
+pub mod crypto;
+pub mod error;
+pub mod exif;
+pub mod ihdr;
+pub mod image;
+pub mod inspect;
+pub mod palette;
+pub mod payload;
+pub mod physical;
+pub mod png;
+pub mod png_ref;
+pub mod reader;
+pub mod signature;
+pub mod split;
+pub mod strip;
+pub mod text;
+pub mod time;
+pub mod validation;
+pub mod writer;
+
+pub use crate::chunk::Chunk;
+pub use crate::chunk_ref::ChunkRef;
+pub use crate::chunk_type::ChunkType;
+pub use crate::color::{Background, Chromaticities, Gamma, IccProfile, RenderingIntent, SignificantBits};
+pub use crate::error::PngyinxError;
+pub use crate::exif::Exif;
+pub use crate::ihdr::Ihdr;
+pub use crate::image::Image;
+pub use crate::palette::{Palette, Transparency};
+pub use crate::physical::PhysicalDimensions;
+pub use crate::png::Png;
+pub use crate::png_ref::PngRef;
+pub use crate::reader::PngReader;
+pub use crate::text::TextChunk;
+pub use crate::time::ImageTime;
+pub use crate::writer::PngWriter;
+
+pub type Error = PngyinxError;
+pub type Result<T> = std::result::Result<T, Error>;
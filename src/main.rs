@@ -1,13 +1,9 @@
-use crate::args::{args_processing};
+use pngyinx::Result;
+use crate::args::args_processing;
 
 mod args;
-mod chunk;
-mod chunk_type;
 mod commands;
-mod png;
-
-pub type Error = Box<dyn std::error::Error>;
-pub type Result<T> = std::result::Result<T, Error>;
+mod output;
 
 fn main() -> Result<()> {
     args_processing();
@@ -0,0 +1,29 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// How read-only commands print their results.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+pub fn print_json(value: &impl Serialize) {
+    println!("{}", serde_json::to_string_pretty(value).expect("Unable to serialize the output"));
+}
+
+/// An error raised by the CLI itself rather than the library, shaped like a serialized `pngyinx::Error`.
+pub fn cli_error(code: &str, message: String) -> Value {
+    json!({ "kind": "cli", "code": code, "message": message })
+}
+
+/// Gives up on a command: panics with `message` in text mode, prints `error` as JSON and exits otherwise.
+pub fn fail(format: Format, error: pngyinx::Error, message: &str) -> ! {
+    match format {
+        Format::Text => panic!("{}", message),
+        Format::Json => {
+            print_json(&json!({ "error": error }));
+            std::process::exit(1)
+        },
+    }
+}
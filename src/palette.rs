@@ -0,0 +1,264 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::ihdr::{ColorType, Ihdr};
+use crate::palette::PaletteError::{EmptyPalette, MissingPalette, NotAllowed, SampleTooLarge, TooManyEntries, WrongLength};
+use crate::Result;
+
+pub const PLTE_CHUNK_TYPE: &str = "PLTE";
+pub const TRNS_CHUNK_TYPE: &str = "tRNS";
+
+/// The colors of an indexed image, from PLTE. Truecolor images may carry one as a hint for
+/// viewers that can't show all their colors.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Palette {
+    entries: Vec<[u8; 3]>,
+}
+
+impl Palette {
+    /// A palette holds between 1 and 256 entries.
+    pub fn new(entries: Vec<[u8; 3]>) -> Result<Palette> {
+        if entries.is_empty() {
+            return Err(EmptyPalette.into());
+        }
+        if entries.len() > 256 {
+            return Err(TooManyEntries(entries.len(), 256).into());
+        }
+        Ok(Palette { entries })
+    }
+
+    pub fn from_data(data: &[u8]) -> Result<Palette> {
+        if !data.len().is_multiple_of(3) {
+            return Err(WrongLength(PLTE_CHUNK_TYPE, data.len()).into());
+        }
+        Palette::new(data.chunks(3).map(|entry| [entry[0], entry[1], entry[2]]).collect())
+    }
+
+    pub fn entries(&self) -> &[[u8; 3]] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Checks that the image described by `ihdr` may have this palette: never for grayscale
+    /// images, and for indexed ones no more entries than the bit depth can address.
+    /// `ihdr` is checked first, as its bit depth may come straight from the file.
+    pub fn check(&self, ihdr: &Ihdr) -> Result<()> {
+        ihdr.check()?;
+        match ihdr.color_type {
+            ColorType::Grayscale | ColorType::GrayscaleAlpha => Err(NotAllowed(PLTE_CHUNK_TYPE, ihdr.color_type).into()),
+            ColorType::Indexed if self.len() > 1 << ihdr.bit_depth => Err(TooManyEntries(self.len(), 1 << ihdr.bit_depth).into()),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        Ok(Chunk::new(ChunkType::from_str(PLTE_CHUNK_TYPE)?, self.entries.concat()))
+    }
+}
+
+/// The transparency of an image without an alpha channel, from tRNS: an alpha value per
+/// palette entry, or the one gray level or color that is fully transparent.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "snake_case"))]
+pub enum Transparency {
+    Indexed(Vec<u8>),
+    Grayscale(u16),
+    Truecolor(u16, u16, u16),
+}
+
+impl Transparency {
+    /// Parses the data of a tRNS chunk, whose layout depends on the color type of the image.
+    pub fn from_data(data: &[u8], ihdr: &Ihdr) -> Result<Transparency> {
+        let sample = |index: usize| u16::from_be_bytes([data[index], data[index + 1]]);
+        let transparency = match ihdr.color_type {
+            ColorType::Indexed => Transparency::Indexed(data.to_vec()),
+            ColorType::Grayscale if data.len() == 2 => Transparency::Grayscale(sample(0)),
+            ColorType::Truecolor if data.len() == 6 => Transparency::Truecolor(sample(0), sample(2), sample(4)),
+            ColorType::Grayscale | ColorType::Truecolor => return Err(WrongLength(TRNS_CHUNK_TYPE, data.len()).into()),
+            ColorType::GrayscaleAlpha | ColorType::TruecolorAlpha => return Err(NotAllowed(TRNS_CHUNK_TYPE, ihdr.color_type).into()),
+        };
+        transparency.check(ihdr, None)?;
+        Ok(transparency)
+    }
+
+    /// Checks that the transparency matches the color type and bit depth of `ihdr`, and that
+    /// there are no more alpha values than entries in `palette`.
+    pub fn check(&self, ihdr: &Ihdr, palette: Option<&Palette>) -> Result<()> {
+        ihdr.check()?;
+        let max_sample = (1u32 << ihdr.bit_depth) - 1;
+        match (self, ihdr.color_type) {
+            (Transparency::Indexed(alphas), ColorType::Indexed) => {
+                let max_entries = palette.map_or(1 << ihdr.bit_depth, Palette::len);
+                if alphas.len() > max_entries {
+                    return Err(TooManyEntries(alphas.len(), max_entries).into());
+                }
+            },
+            (Transparency::Grayscale(gray), ColorType::Grayscale) => {
+                if *gray as u32 > max_sample {
+                    return Err(SampleTooLarge(*gray, ihdr.bit_depth).into());
+                }
+            },
+            (Transparency::Truecolor(red, green, blue), ColorType::Truecolor) => {
+                if let Some(sample) = [*red, *green, *blue].into_iter().find(|&sample| sample as u32 > max_sample) {
+                    return Err(SampleTooLarge(sample, ihdr.bit_depth).into());
+                }
+            },
+            _ => return Err(NotAllowed(TRNS_CHUNK_TYPE, ihdr.color_type).into()),
+        }
+        Ok(())
+    }
+
+    pub fn to_data(&self) -> Vec<u8> {
+        match self {
+            Transparency::Indexed(alphas) => alphas.clone(),
+            Transparency::Grayscale(gray) => gray.to_be_bytes().to_vec(),
+            Transparency::Truecolor(red, green, blue) => [red.to_be_bytes(), green.to_be_bytes(), blue.to_be_bytes()].concat(),
+        }
+    }
+
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        Ok(Chunk::new(ChunkType::from_str(TRNS_CHUNK_TYPE)?, self.to_data()))
+    }
+}
+
+#[derive(Debug)]
+pub enum PaletteError {
+    EmptyPalette,
+    WrongLength(&'static str, usize),
+    TooManyEntries(usize, usize),
+    NotAllowed(&'static str, ColorType),
+    MissingPalette,
+    SampleTooLarge(u16, u8),
+}
+
+impl Display for PaletteError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EmptyPalette => write!(f, "A palette needs at least one entry"),
+            WrongLength(chunk_type, length) => write!(f, "{} can't be {} bytes long", chunk_type, length),
+            TooManyEntries(entries, max) => write!(f, "{} entries were found but at most {} are allowed", entries, max),
+            NotAllowed(chunk_type, color_type) => write!(f, "{} isn't allowed in {} images", chunk_type, color_type),
+            MissingPalette => write!(f, "Indexed images need a palette"),
+            SampleTooLarge(sample, bit_depth) => write!(f, "The sample {} doesn't fit in {} bits", sample, bit_depth),
+        }
+    }
+}
+
+impl PaletteError {
+    /// A stable identifier for the error, for machine-readable output.
+    pub fn code(&self) -> &'static str {
+        match self {
+            EmptyPalette => "empty_palette",
+            WrongLength(_, _) => "wrong_length",
+            TooManyEntries(_, _) => "too_many_entries",
+            NotAllowed(_, _) => "not_allowed",
+            MissingPalette => "missing_palette",
+            SampleTooLarge(_, _) => "sample_too_large",
+        }
+    }
+}
+
+impl std::error::Error for PaletteError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ihdr::InterlaceMethod;
+    use crate::png::Png;
+    use crate::PngyinxError;
+
+    fn ihdr(color_type: ColorType, bit_depth: u8) -> Ihdr {
+        Ihdr {
+            width: 4,
+            height: 4,
+            bit_depth,
+            color_type,
+            compression_method: 0,
+            filter_method: 0,
+            interlace_method: InterlaceMethod::None,
+        }
+    }
+
+    fn png(ihdr: Ihdr) -> Png {
+        Png::from_chunks(vec![
+            ihdr.to_chunk().unwrap(),
+            Chunk::new(ChunkType::from_str("IDAT").unwrap(), Vec::new()),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ])
+    }
+
+    #[test]
+    fn test_palette_round_trip() {
+        let palette = Palette::new(vec![[255, 0, 0], [0, 255, 0], [0, 0, 255]]).unwrap();
+        let chunk = palette.to_chunk().unwrap();
+        assert_eq!(chunk.data(), &[255, 0, 0, 0, 255, 0, 0, 0, 255]);
+        assert_eq!(Palette::from_data(chunk.data()).unwrap(), palette);
+        assert!(matches!(Palette::from_data(&[1, 2]), Err(PngyinxError::Palette(WrongLength("PLTE", 2)))));
+        assert!(matches!(Palette::new(Vec::new()), Err(PngyinxError::Palette(EmptyPalette))));
+    }
+
+    #[test]
+    fn test_palette_against_bit_depth() {
+        let palette = Palette::new(vec![[0, 0, 0]; 5]).unwrap();
+        assert!(matches!(palette.check(&ihdr(ColorType::Indexed, 2)), Err(PngyinxError::Palette(TooManyEntries(5, 4)))));
+        assert!(palette.check(&ihdr(ColorType::Indexed, 4)).is_ok());
+        assert!(palette.check(&ihdr(ColorType::Truecolor, 8)).is_ok());
+        assert!(matches!(palette.check(&ihdr(ColorType::Grayscale, 8)), Err(PngyinxError::Palette(NotAllowed("PLTE", _)))));
+        assert!(matches!(palette.check(&ihdr(ColorType::Indexed, 64)), Err(PngyinxError::Ihdr(_))));
+        assert!(matches!(Transparency::Grayscale(0).check(&ihdr(ColorType::Grayscale, 32), None), Err(PngyinxError::Ihdr(_))));
+    }
+
+    #[test]
+    fn test_transparency() {
+        let indexed = ihdr(ColorType::Indexed, 8);
+        let transparency = Transparency::from_data(&[0, 128], &indexed).unwrap();
+        assert_eq!(transparency, Transparency::Indexed(vec![0, 128]));
+        let palette = Palette::new(vec![[0, 0, 0]]).unwrap();
+        assert!(matches!(transparency.check(&indexed, Some(&palette)), Err(PngyinxError::Palette(TooManyEntries(2, 1)))));
+
+        let truecolor = ihdr(ColorType::Truecolor, 8);
+        let transparency = Transparency::from_data(&[0, 1, 0, 2, 0, 3], &truecolor).unwrap();
+        assert_eq!(transparency, Transparency::Truecolor(1, 2, 3));
+        assert_eq!(transparency.to_data(), vec![0, 1, 0, 2, 0, 3]);
+        assert!(matches!(Transparency::from_data(&[1, 0], &ihdr(ColorType::Grayscale, 8)), Err(PngyinxError::Palette(SampleTooLarge(256, 8)))));
+        assert!(matches!(Transparency::from_data(&[0, 0], &ihdr(ColorType::TruecolorAlpha, 8)), Err(PngyinxError::Palette(NotAllowed("tRNS", _)))));
+    }
+
+    #[test]
+    fn test_set_palette() {
+        let mut png = png(ihdr(ColorType::Indexed, 8));
+        assert_eq!(png.palette().unwrap(), None);
+        png.set_transparency(&Transparency::Indexed(vec![0])).unwrap_err();
+
+        let palette = Palette::new(vec![[1, 2, 3], [4, 5, 6]]).unwrap();
+        png.set_palette(&palette).unwrap();
+        png.set_transparency(&Transparency::Indexed(vec![0, 255])).unwrap();
+        let types: Vec<String> = png.chunks().iter().map(|chunk| chunk.chunk_type().to_string()).collect();
+        assert_eq!(types, vec!["IHDR", "PLTE", "tRNS", "IDAT", "IEND"]);
+        assert!(png.validate().is_empty());
+
+        let smaller = Palette::new(vec![[7, 8, 9]]).unwrap();
+        assert!(matches!(png.set_palette(&smaller), Err(PngyinxError::Palette(TooManyEntries(2, 1)))));
+        let bigger = Palette::new(vec![[7, 8, 9]; 3]).unwrap();
+        png.set_palette(&bigger).unwrap();
+        assert_eq!(png.palette().unwrap(), Some(bigger));
+        assert_eq!(png.transparency().unwrap(), Some(Transparency::Indexed(vec![0, 255])));
+        assert_eq!(png.chunks().len(), 5);
+    }
+
+    #[test]
+    fn test_set_palette_on_grayscale() {
+        let mut png = png(ihdr(ColorType::Grayscale, 8));
+        let palette = Palette::new(vec![[0, 0, 0]]).unwrap();
+        assert!(matches!(png.set_palette(&palette), Err(PngyinxError::Palette(NotAllowed("PLTE", _)))));
+    }
+}
@@ -0,0 +1,270 @@
+use std::fmt::{Display, Formatter};
+use std::io::{Read, Write};
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use crate::payload::PayloadError::{DecompressionFailed, InvalidMetadata, MetadataTooLong, SizeMismatch, TooLarge, TruncatedHeader, UnknownFlags, UnsupportedVersion};
+use crate::{crypto, split};
+use crate::Result;
+
+/// Payloads that need a header start with these bytes, followed by the format version and the flags.
+/// Anything else is a raw message, which is what `encode` writes when no option is used.
+pub const MAGIC: [u8; 3] = *b"PYP";
+pub const VERSION: u8 = 1;
+
+pub const FLAG_COMPRESSED: u8 = 0b0000_0001;
+pub const FLAG_FILE: u8 = 0b0000_0010;
+const KNOWN_FLAGS: u8 = FLAG_COMPRESSED | FLAG_FILE;
+const HEADER_LENGTH: usize = 5;
+
+/// The default limit on the size of an inflated payload.
+pub const DEFAULT_MAX_SIZE: usize = 64 * 1024 * 1024;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PayloadOptions {
+    /// The zlib level, from 0 to 9, or `None` to store the message as is.
+    pub compression: Option<u32>,
+    /// Set when the message is the content of a file.
+    pub file: Option<FileMetadata>,
+}
+
+/// What is kept of an embedded file besides its bytes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FileMetadata {
+    pub name: String,
+    pub size: u64,
+    pub mime: Option<String>,
+}
+
+/// A decoded payload: the message and, if it was a file, its metadata.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Payload {
+    pub data: Vec<u8>,
+    pub file: Option<FileMetadata>,
+}
+
+/// Whether `data` starts with a payload header.
+pub fn has_header(data: &[u8]) -> bool {
+    data.starts_with(&MAGIC)
+}
+
+/// Builds the bytes to embed for `message`, adding a header only when an option needs one
+/// or when the raw message would be mistaken for a header, ours or that of another layer.
+pub fn encode_payload(message: &[u8], options: &PayloadOptions) -> Result<Vec<u8>> {
+    let ambiguous = [MAGIC, crypto::MAGIC, split::MAGIC].iter().any(|magic| message.starts_with(magic));
+    if options.compression.is_none() && options.file.is_none() && !ambiguous {
+        return Ok(message.to_vec());
+    }
+    let mut flags = 0;
+    if options.compression.is_some() {
+        flags |= FLAG_COMPRESSED;
+    }
+    if options.file.is_some() {
+        flags |= FLAG_FILE;
+    }
+    let mut payload = MAGIC.to_vec();
+    payload.push(VERSION);
+    payload.push(flags);
+    if let Some(file) = &options.file {
+        let name = file.name.as_bytes();
+        let mime = file.mime.as_deref().unwrap_or("").as_bytes();
+        if name.len() > u16::MAX as usize || mime.len() > u8::MAX as usize {
+            return Err(MetadataTooLong.into());
+        }
+        payload.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        payload.extend_from_slice(name);
+        payload.extend_from_slice(&file.size.to_be_bytes());
+        payload.push(mime.len() as u8);
+        payload.extend_from_slice(mime);
+    }
+    match options.compression {
+        Some(level) => {
+            let mut encoder = ZlibEncoder::new(payload, Compression::new(level.min(9)));
+            encoder.write_all(message)?;
+            Ok(encoder.finish()?)
+        },
+        None => {
+            payload.extend_from_slice(message);
+            Ok(payload)
+        },
+    }
+}
+
+/// Gives back the message stored by `encode_payload`, refusing to inflate more than `max_size` bytes.
+pub fn decode_payload(data: &[u8], max_size: usize) -> Result<Payload> {
+    if !has_header(data) {
+        return Ok(Payload { data: data.to_vec(), file: None });
+    }
+    let header = data.get(..HEADER_LENGTH).ok_or(TruncatedHeader)?;
+    if header[3] != VERSION {
+        return Err(UnsupportedVersion(header[3]).into());
+    }
+    let flags = header[4];
+    if flags & !KNOWN_FLAGS != 0 {
+        return Err(UnknownFlags(flags).into());
+    }
+    let mut body = &data[HEADER_LENGTH..];
+    let file = if flags & FLAG_FILE != 0 {
+        Some(read_file_metadata(&mut body)?)
+    } else {
+        None
+    };
+    let data = if flags & FLAG_COMPRESSED != 0 {
+        inflate(body, max_size)?
+    } else {
+        body.to_vec()
+    };
+    if let Some(file) = &file {
+        if file.size != data.len() as u64 {
+            return Err(SizeMismatch(file.size, data.len() as u64).into());
+        }
+    }
+    Ok(Payload { data, file })
+}
+
+fn read_file_metadata(body: &mut &[u8]) -> Result<FileMetadata> {
+    let name_length = u16::from_be_bytes(take(body, 2)?.try_into().unwrap()) as usize;
+    let name = String::from_utf8(take(body, name_length)?.to_vec()).map_err(|_| InvalidMetadata)?;
+    let size = u64::from_be_bytes(take(body, 8)?.try_into().unwrap());
+    let mime_length = take(body, 1)?[0] as usize;
+    let mime = String::from_utf8(take(body, mime_length)?.to_vec()).map_err(|_| InvalidMetadata)?;
+    Ok(FileMetadata {
+        name,
+        size,
+        mime: if mime.is_empty() { None } else { Some(mime) },
+    })
+}
+
+fn take<'a>(body: &mut &'a [u8], length: usize) -> Result<&'a [u8]> {
+    let (taken, rest) = body.split_at_checked(length).ok_or(TruncatedHeader)?;
+    *body = rest;
+    Ok(taken)
+}
+
+fn inflate(data: &[u8], max_size: usize) -> Result<Vec<u8>> {
+    let mut message = Vec::new();
+    ZlibDecoder::new(data)
+        .take((max_size as u64).saturating_add(1))
+        .read_to_end(&mut message)
+        .map_err(|_| DecompressionFailed)?;
+    if message.len() > max_size {
+        return Err(TooLarge(max_size).into());
+    }
+    Ok(message)
+}
+
+#[derive(Debug)]
+pub enum PayloadError {
+    UnsupportedVersion(u8),
+    UnknownFlags(u8),
+    TruncatedHeader,
+    DecompressionFailed,
+    TooLarge(usize),
+    MetadataTooLong,
+    InvalidMetadata,
+    SizeMismatch(u64, u64),
+}
+
+impl Display for PayloadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnsupportedVersion(version) => write!(f, "Unsupported payload version: {}", version),
+            UnknownFlags(flags) => write!(f, "Unknown payload flags: {:#010b}", flags),
+            TruncatedHeader => write!(f, "The payload header is truncated"),
+            DecompressionFailed => write!(f, "The payload couldn't be decompressed"),
+            TooLarge(max_size) => write!(f, "The payload inflates to more than {} bytes", max_size),
+            MetadataTooLong => write!(f, "The file name or MIME type is too long"),
+            InvalidMetadata => write!(f, "The file name or MIME type isn't valid UTF-8"),
+            SizeMismatch(expected, found) => write!(f, "The file should be {} bytes long but {} were found", expected, found),
+        }
+    }
+}
+
+impl PayloadError {
+    /// A stable identifier for the error, for machine-readable output.
+    pub fn code(&self) -> &'static str {
+        match self {
+            UnsupportedVersion(_) => "unsupported_version",
+            UnknownFlags(_) => "unknown_flags",
+            TruncatedHeader => "truncated_header",
+            DecompressionFailed => "decompression_failed",
+            TooLarge(_) => "too_large",
+            MetadataTooLong => "metadata_too_long",
+            InvalidMetadata => "invalid_metadata",
+            SizeMismatch(_, _) => "size_mismatch",
+        }
+    }
+}
+
+impl std::error::Error for PayloadError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PngyinxError;
+
+    #[test]
+    fn test_raw_payload() {
+        let payload = encode_payload(b"Hello", &PayloadOptions::default()).unwrap();
+        assert_eq!(payload, b"Hello");
+        assert_eq!(decode_payload(&payload, DEFAULT_MAX_SIZE).unwrap().data, b"Hello");
+
+        let payload = encode_payload(b"PYX is not encrypted", &PayloadOptions::default()).unwrap();
+        assert!(!crypto::is_encrypted(&payload));
+        assert_eq!(decode_payload(&payload, DEFAULT_MAX_SIZE).unwrap().data, b"PYX is not encrypted");
+
+        for message in [&b"PYPI token"[..], b"PYS is not a fragment"] {
+            let payload = encode_payload(message, &PayloadOptions::default()).unwrap();
+            assert!(has_header(&payload));
+            assert!(!split::is_fragment(&payload));
+            assert_eq!(decode_payload(&payload, DEFAULT_MAX_SIZE).unwrap().data, message);
+        }
+    }
+
+    #[test]
+    fn test_compressed_payload() {
+        let message = "All work and no play makes Jack a dull boy. ".repeat(100);
+        let payload = encode_payload(message.as_bytes(), &PayloadOptions { compression: Some(9), file: None }).unwrap();
+        assert!(has_header(&payload));
+        assert!(payload.len() < message.len() / 10);
+        assert_eq!(decode_payload(&payload, DEFAULT_MAX_SIZE).unwrap().data, message.as_bytes());
+    }
+
+    #[test]
+    fn test_decompression_limit() {
+        let message = vec![0u8; 100_000];
+        let payload = encode_payload(&message, &PayloadOptions { compression: Some(6), file: None }).unwrap();
+        assert!(matches!(decode_payload(&payload, 1000), Err(PngyinxError::Payload(TooLarge(1000)))));
+        assert_eq!(decode_payload(&payload, 100_000).unwrap().data.len(), 100_000);
+        assert_eq!(decode_payload(&payload, usize::MAX).unwrap().data.len(), 100_000);
+    }
+
+    #[test]
+    fn test_file_payload() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        let file = FileMetadata { name: String::from("bytes.bin"), size: 256, mime: Some(String::from("application/octet-stream")) };
+        for compression in [None, Some(6)] {
+            let options = PayloadOptions { compression, file: Some(file.clone()) };
+            let payload = decode_payload(&encode_payload(&bytes, &options).unwrap(), DEFAULT_MAX_SIZE).unwrap();
+            assert_eq!(payload.data, bytes);
+            assert_eq!(payload.file, Some(file.clone()));
+        }
+    }
+
+    #[test]
+    fn test_file_size_mismatch() {
+        let file = FileMetadata { name: String::from("a.txt"), size: 10, mime: None };
+        let payload = encode_payload(b"Hello", &PayloadOptions { compression: None, file: Some(file) }).unwrap();
+        assert!(matches!(decode_payload(&payload, DEFAULT_MAX_SIZE), Err(PngyinxError::Payload(SizeMismatch(10, 5)))));
+        assert!(matches!(decode_payload(&payload[..8], DEFAULT_MAX_SIZE), Err(PngyinxError::Payload(TruncatedHeader))));
+    }
+
+    #[test]
+    fn test_invalid_payloads() {
+        assert!(matches!(decode_payload(b"PYP", DEFAULT_MAX_SIZE), Err(PngyinxError::Payload(TruncatedHeader))));
+        assert!(matches!(decode_payload(b"PYP\x02\x01", DEFAULT_MAX_SIZE), Err(PngyinxError::Payload(UnsupportedVersion(2)))));
+        assert!(matches!(decode_payload(b"PYP\x01\x80", DEFAULT_MAX_SIZE), Err(PngyinxError::Payload(UnknownFlags(0x80)))));
+        assert!(matches!(decode_payload(b"PYP\x01\x01garbage", DEFAULT_MAX_SIZE), Err(PngyinxError::Payload(DecompressionFailed))));
+    }
+}
@@ -0,0 +1,136 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::physical::PhysicalError::{UnknownUnit, WrongLength};
+use crate::Result;
+
+pub const PHYS_CHUNK_TYPE: &str = "pHYs";
+const LENGTH: usize = 9;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "snake_case"))]
+pub enum Unit {
+    /// Only the aspect ratio of the pixels is known.
+    Unknown = 0,
+    Meter = 1,
+}
+
+/// The intended pixel size or aspect ratio, from pHYs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PhysicalDimensions {
+    pub pixels_per_unit_x: u32,
+    pub pixels_per_unit_y: u32,
+    pub unit: Unit,
+}
+
+impl Display for PhysicalDimensions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.unit {
+            Unit::Unknown => write!(f, "{}:{} pixel aspect ratio", self.pixels_per_unit_x, self.pixels_per_unit_y),
+            Unit::Meter => match self.dpi() {
+                Some((x, y)) if x == y => write!(f, "{} dpi", x),
+                _ => write!(f, "{}x{} pixels per meter", self.pixels_per_unit_x, self.pixels_per_unit_y),
+            },
+        }
+    }
+}
+
+impl PhysicalDimensions {
+    pub fn from_data(data: &[u8]) -> Result<PhysicalDimensions> {
+        if data.len() != LENGTH {
+            return Err(WrongLength(data.len()).into());
+        }
+        let unit = match data[8] {
+            0 => Unit::Unknown,
+            1 => Unit::Meter,
+            unit => return Err(UnknownUnit(unit).into()),
+        };
+        Ok(PhysicalDimensions {
+            pixels_per_unit_x: u32::from_be_bytes(data[0..4].try_into().unwrap()),
+            pixels_per_unit_y: u32::from_be_bytes(data[4..8].try_into().unwrap()),
+            unit,
+        })
+    }
+
+    /// The resolution in dots per inch, rounded, when the unit is the meter.
+    pub fn dpi(&self) -> Option<(u32, u32)> {
+        let dpi = |pixels_per_meter: u32| (pixels_per_meter as f64 * 0.0254).round() as u32;
+        match self.unit {
+            Unit::Meter => Some((dpi(self.pixels_per_unit_x), dpi(self.pixels_per_unit_y))),
+            Unit::Unknown => None,
+        }
+    }
+
+    pub fn to_data(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(LENGTH);
+        data.extend_from_slice(&self.pixels_per_unit_x.to_be_bytes());
+        data.extend_from_slice(&self.pixels_per_unit_y.to_be_bytes());
+        data.push(self.unit as u8);
+        data
+    }
+
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        Ok(Chunk::new(ChunkType::from_str(PHYS_CHUNK_TYPE)?, self.to_data()))
+    }
+}
+
+#[derive(Debug)]
+pub enum PhysicalError {
+    WrongLength(usize),
+    UnknownUnit(u8),
+}
+
+impl Display for PhysicalError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WrongLength(length) => write!(f, "pHYs must be 9 bytes long, not {}", length),
+            UnknownUnit(unit) => write!(f, "Unknown unit specifier: {}", unit),
+        }
+    }
+}
+
+impl PhysicalError {
+    /// A stable identifier for the error, for machine-readable output.
+    pub fn code(&self) -> &'static str {
+        match self {
+            WrongLength(_) => "wrong_length",
+            UnknownUnit(_) => "unknown_unit",
+        }
+    }
+}
+
+impl std::error::Error for PhysicalError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::Png;
+    use crate::PngyinxError;
+
+    const PNG_FILE: &[u8] = include_bytes!("../pictures/dice.png");
+
+    #[test]
+    fn test_physical_dimensions() {
+        let dimensions = PhysicalDimensions { pixels_per_unit_x: 11811, pixels_per_unit_y: 11811, unit: Unit::Meter };
+        let chunk = dimensions.to_chunk().unwrap();
+        assert_eq!(PhysicalDimensions::from_data(chunk.data()).unwrap(), dimensions);
+        assert_eq!(dimensions.to_string(), "300 dpi");
+        assert!(matches!(PhysicalDimensions::from_data(&chunk.data()[..8]), Err(PngyinxError::Physical(WrongLength(8)))));
+        let mut data = chunk.data().to_vec();
+        data[8] = 2;
+        assert!(matches!(PhysicalDimensions::from_data(&data), Err(PngyinxError::Physical(UnknownUnit(2)))));
+    }
+
+    #[test]
+    fn test_png_physical_dimensions() {
+        let mut png = Png::try_from(PNG_FILE).unwrap();
+        assert!(png.physical_dimensions().unwrap().is_some());
+        let dimensions = PhysicalDimensions { pixels_per_unit_x: 1, pixels_per_unit_y: 2, unit: Unit::Unknown };
+        png.set_physical_dimensions(&dimensions).unwrap();
+        assert_eq!(png.physical_dimensions().unwrap(), Some(dimensions));
+        assert_eq!(png.chunks_by_type(PHYS_CHUNK_TYPE).len(), 1);
+        assert_eq!(dimensions.to_string(), "1:2 pixel aspect ratio");
+    }
+}
@@ -1,9 +1,28 @@
 use std::fmt::{Display, Formatter};
+use std::io::{Read, Write};
 use crate::chunk::Chunk;
 use crate::Error;
-use crate::png::PngError::{NonExistantChunk, IncorrectHeader};
+use crate::color::{Background, Chromaticities, Gamma, IccProfile, RenderingIntent, SignificantBits, BKGD_CHUNK_TYPE, CHRM_CHUNK_TYPE, GAMA_CHUNK_TYPE, ICCP_CHUNK_TYPE, SBIT_CHUNK_TYPE, SRGB_CHUNK_TYPE};
+use crate::exif::{self, Exif, EXIF_CHUNK_TYPE};
+use crate::image::Image;
+use crate::ihdr::{ColorType, Ihdr, IHDR_CHUNK_TYPE};
+use crate::palette::{Palette, Transparency, PLTE_CHUNK_TYPE, TRNS_CHUNK_TYPE};
+use crate::palette::PaletteError::MissingPalette;
+use crate::png::PngError::{NonExistantChunk, IncorrectHeader, IndexOutOfBounds, InvalidStructure};
+use crate::physical::{PhysicalDimensions, PHYS_CHUNK_TYPE};
+use crate::png_ref::PngRef;
+use crate::reader::PngReader;
+use crate::strip::StripPolicy;
+use crate::text::TextChunk;
+use crate::time::{ImageTime, TIME_CHUNK_TYPE};
+use crate::validation::{validate, Violation};
+use crate::writer::PngWriter;
 use crate::Result;
 
+/// The chunks that gAMA, cHRM, sRGB, iCCP and sBIT must come before.
+const COLOR_SPACE_SUCCESSORS: [&str; 5] = ["PLTE", "tRNS", "bKGD", "IDAT", "IEND"];
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Png {
     chunks: Vec<Chunk>,
 }
@@ -11,29 +30,94 @@ pub struct Png {
 impl Png {
     pub const STANDARD_HEADER: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
 
-    fn from_chunks(chunks: Vec<Chunk>) -> Png {
+    pub fn from_chunks(chunks: Vec<Chunk>) -> Png {
         Self {
             chunks
         }
     }
 
+    pub fn from_reader<R: Read>(reader: R) -> Result<Png> {
+        let chunks = PngReader::new(reader)?.collect::<Result<Vec<Chunk>>>()?;
+        Ok(Self::from_chunks(chunks))
+    }
+
+    /// Parses `value` like `try_from`, but also rejects files that break the chunk ordering rules.
+    pub fn try_from_strict(value: &[u8]) -> Result<Png> {
+        let png = Png::try_from(value)?;
+        let violations = png.validate();
+        if violations.is_empty() {
+            Ok(png)
+        } else {
+            Err(InvalidStructure(violations).into())
+        }
+    }
+
+    pub fn validate(&self) -> Vec<Violation> {
+        validate(&self.chunks)
+    }
+
     pub fn append_chunk(&mut self, chunk: Chunk) {
         self.chunks.push(chunk)
     }
 
+    pub fn insert_at(&mut self, index: usize, chunk: Chunk) -> Result<()> {
+        if index > self.chunks.len() {
+            return Err(IndexOutOfBounds(index).into());
+        }
+        self.chunks.insert(index, chunk);
+        Ok(())
+    }
+
+    /// Inserts `chunk` right after the first chunk of type `chunk_type`.
+    pub fn insert_after(&mut self, chunk_type: &str, chunk: Chunk) -> Result<()> {
+        let index = self.chunks.iter().position(|chunk| chunk.chunk_type().to_string().as_str() == chunk_type);
+        match index {
+            Some(index) => self.insert_at(index + 1, chunk),
+            None => Err(NonExistantChunk.into())
+        }
+    }
+
+    /// Removes every chunk of `chunk_type` and returns them in file order.
+    pub fn remove_chunks_by_type(&mut self, chunk_type: &str) -> Result<Vec<Chunk>> {
+        let (removed, kept) = std::mem::take(&mut self.chunks)
+            .into_iter()
+            .partition(|chunk| chunk.chunk_type().to_string().as_str() == chunk_type);
+        self.chunks = kept;
+        if removed.is_empty() {
+            return Err(NonExistantChunk.into());
+        }
+        Ok(removed)
+    }
+
+    /// Inserts `chunk` just before IEND, or at the end if there is no IEND.
+    pub fn insert_before_iend(&mut self, chunk: Chunk) {
+        let index = self.chunks.iter().position(|chunk| chunk.chunk_type().to_string().as_str() == "IEND");
+        self.chunks.insert(index.unwrap_or(self.chunks.len()), chunk)
+    }
+
     pub fn remove_chunk(&mut self, chunk_type: &str) -> Result<Chunk> {
         let index = self.chunks.iter().position(|chunk| chunk.chunk_type().to_string().as_str() == chunk_type);
         match index {
             Some(chunk) => Ok(self.chunks.remove(chunk)),
-            None => Err(Box::new(NonExistantChunk))
+            None => Err(NonExistantChunk.into())
         }
     }
 
-    fn header(&self) -> &[u8; 8] {
+    pub fn header(&self) -> &[u8; 8] {
         &Self::STANDARD_HEADER
     }
 
-    fn chunks(&self) -> &[Chunk] {
+    /// Removes the chunks that `policy` strips and returns them.
+    pub fn strip(&mut self, policy: &StripPolicy) -> Result<Vec<Chunk>> {
+        policy.check()?;
+        let (removed, kept) = std::mem::take(&mut self.chunks)
+            .into_iter()
+            .partition(|chunk| policy.strips(chunk.chunk_type()));
+        self.chunks = kept;
+        Ok(removed)
+    }
+
+    pub fn chunks(&self) -> &[Chunk] {
         self.chunks.as_slice()
     }
 
@@ -41,6 +125,277 @@ impl Png {
         self.chunks.iter().find(|&chunk| chunk.chunk_type().to_string().as_str() == chunk_type)
     }
 
+    /// The parsed image header, from the first IHDR chunk.
+    pub fn ihdr(&self) -> Result<Ihdr> {
+        let chunk = self.chunk_by_type(IHDR_CHUNK_TYPE).ok_or(NonExistantChunk)?;
+        Ihdr::try_from(chunk)
+    }
+
+    /// The parsed palette, checked against IHDR, or `None` if there is no PLTE chunk.
+    pub fn palette(&self) -> Result<Option<Palette>> {
+        let Some(chunk) = self.chunk_by_type(PLTE_CHUNK_TYPE) else {
+            return Ok(None);
+        };
+        let palette = Palette::from_data(chunk.data())?;
+        palette.check(&self.ihdr()?)?;
+        Ok(Some(palette))
+    }
+
+    /// Replaces the PLTE chunk, or adds one where the specification wants it: before IDAT and
+    /// before the chunks that refer to the palette.
+    pub fn set_palette(&mut self, palette: &Palette) -> Result<()> {
+        let ihdr = self.ihdr()?;
+        palette.check(&ihdr)?;
+        if let Some(transparency) = self.transparency()? {
+            transparency.check(&ihdr, Some(palette))?;
+        }
+        self.replace_or_insert_before(palette.to_chunk()?, &["tRNS", "bKGD", "hIST", "IDAT", "IEND"]);
+        Ok(())
+    }
+
+    /// The parsed transparency, checked against IHDR and PLTE, or `None` if there is no tRNS chunk.
+    pub fn transparency(&self) -> Result<Option<Transparency>> {
+        let Some(chunk) = self.chunk_by_type(TRNS_CHUNK_TYPE) else {
+            return Ok(None);
+        };
+        let ihdr = self.ihdr()?;
+        let transparency = Transparency::from_data(chunk.data(), &ihdr)?;
+        transparency.check(&ihdr, self.palette()?.as_ref())?;
+        Ok(Some(transparency))
+    }
+
+    /// Replaces the tRNS chunk, or adds one after PLTE and before IDAT.
+    pub fn set_transparency(&mut self, transparency: &Transparency) -> Result<()> {
+        let ihdr = self.ihdr()?;
+        let palette = self.palette()?;
+        if ihdr.color_type == ColorType::Indexed && palette.is_none() {
+            return Err(MissingPalette.into());
+        }
+        transparency.check(&ihdr, palette.as_ref())?;
+        let chunk = transparency.to_chunk()?;
+        match self.position(TRNS_CHUNK_TYPE) {
+            Some(index) => self.chunks[index] = chunk,
+            None => {
+                let index = match self.position(PLTE_CHUNK_TYPE) {
+                    Some(index) => index + 1,
+                    None => self.position("IDAT").or(self.position("IEND")).unwrap_or(self.chunks.len()),
+                };
+                self.chunks.insert(index, chunk);
+            },
+        }
+        Ok(())
+    }
+
+    pub fn gamma(&self) -> Result<Option<Gamma>> {
+        self.chunk_by_type(GAMA_CHUNK_TYPE).map(|chunk| Gamma::from_data(chunk.data())).transpose()
+    }
+
+    pub fn set_gamma(&mut self, gamma: &Gamma) -> Result<()> {
+        self.replace_or_insert_before(gamma.to_chunk()?, &COLOR_SPACE_SUCCESSORS);
+        Ok(())
+    }
+
+    pub fn chromaticities(&self) -> Result<Option<Chromaticities>> {
+        self.chunk_by_type(CHRM_CHUNK_TYPE).map(|chunk| Chromaticities::from_data(chunk.data())).transpose()
+    }
+
+    pub fn set_chromaticities(&mut self, chromaticities: &Chromaticities) -> Result<()> {
+        self.replace_or_insert_before(chromaticities.to_chunk()?, &COLOR_SPACE_SUCCESSORS);
+        Ok(())
+    }
+
+    /// The rendering intent, or `None` if there is no sRGB chunk.
+    pub fn rendering_intent(&self) -> Result<Option<RenderingIntent>> {
+        self.chunk_by_type(SRGB_CHUNK_TYPE).map(|chunk| RenderingIntent::from_data(chunk.data())).transpose()
+    }
+
+    /// Replaces the sRGB chunk, or adds one before PLTE and IDAT. Any iCCP chunk is removed, as
+    /// the two must not both be present.
+    pub fn set_rendering_intent(&mut self, intent: RenderingIntent) -> Result<()> {
+        let chunk = intent.to_chunk()?;
+        self.chunks.retain(|chunk| chunk.chunk_type().to_string() != ICCP_CHUNK_TYPE);
+        self.replace_or_insert_before(chunk, &COLOR_SPACE_SUCCESSORS);
+        Ok(())
+    }
+
+    pub fn icc_profile(&self) -> Result<Option<IccProfile>> {
+        self.chunk_by_type(ICCP_CHUNK_TYPE).map(|chunk| IccProfile::from_data(chunk.data())).transpose()
+    }
+
+    /// Replaces the iCCP chunk, or adds one before PLTE and IDAT. Any sRGB chunk is removed, as
+    /// the two must not both be present.
+    pub fn set_icc_profile(&mut self, profile: &IccProfile) -> Result<()> {
+        let chunk = profile.to_chunk()?;
+        self.chunks.retain(|chunk| chunk.chunk_type().to_string() != SRGB_CHUNK_TYPE);
+        self.replace_or_insert_before(chunk, &COLOR_SPACE_SUCCESSORS);
+        Ok(())
+    }
+
+    /// The significant bits, checked against IHDR, or `None` if there is no sBIT chunk.
+    pub fn significant_bits(&self) -> Result<Option<SignificantBits>> {
+        let Some(chunk) = self.chunk_by_type(SBIT_CHUNK_TYPE) else {
+            return Ok(None);
+        };
+        Ok(Some(SignificantBits::from_data(chunk.data(), &self.ihdr()?)?))
+    }
+
+    pub fn set_significant_bits(&mut self, significant_bits: &SignificantBits) -> Result<()> {
+        significant_bits.check(&self.ihdr()?)?;
+        self.replace_or_insert_before(significant_bits.to_chunk()?, &COLOR_SPACE_SUCCESSORS);
+        Ok(())
+    }
+
+    /// The background color, checked against IHDR and PLTE, or `None` if there is no bKGD chunk.
+    pub fn background(&self) -> Result<Option<Background>> {
+        let Some(chunk) = self.chunk_by_type(BKGD_CHUNK_TYPE) else {
+            return Ok(None);
+        };
+        let ihdr = self.ihdr()?;
+        let background = Background::from_data(chunk.data(), &ihdr)?;
+        background.check(&ihdr, self.palette()?.as_ref())?;
+        Ok(Some(background))
+    }
+
+    /// Replaces the bKGD chunk, or adds one after PLTE and before IDAT.
+    pub fn set_background(&mut self, background: &Background) -> Result<()> {
+        let ihdr = self.ihdr()?;
+        let palette = self.palette()?;
+        if ihdr.color_type == ColorType::Indexed && palette.is_none() {
+            return Err(MissingPalette.into());
+        }
+        background.check(&ihdr, palette.as_ref())?;
+        self.replace_or_insert_before(background.to_chunk()?, &["hIST", "IDAT", "IEND"]);
+        Ok(())
+    }
+
+    pub fn physical_dimensions(&self) -> Result<Option<PhysicalDimensions>> {
+        self.chunk_by_type(PHYS_CHUNK_TYPE).map(|chunk| PhysicalDimensions::from_data(chunk.data())).transpose()
+    }
+
+    pub fn set_physical_dimensions(&mut self, dimensions: &PhysicalDimensions) -> Result<()> {
+        self.replace_or_insert_before(dimensions.to_chunk()?, &["IDAT", "IEND"]);
+        Ok(())
+    }
+
+    pub fn exif(&self) -> Result<Option<Exif>> {
+        self.chunk_by_type(EXIF_CHUNK_TYPE).map(|chunk| Exif::from_data(chunk.data())).transpose()
+    }
+
+    /// Replaces the eXIf chunk, or adds one before IDAT.
+    pub fn set_exif(&mut self, exif: &Exif) -> Result<()> {
+        self.replace_or_insert_before(exif.to_chunk()?, &["IDAT", "IEND"]);
+        Ok(())
+    }
+
+    /// Removes the GPS tags from the eXIf chunk, leaving the rest of it as it was, and returns
+    /// how many were removed.
+    pub fn erase_gps(&mut self) -> Result<usize> {
+        let index = self.position(EXIF_CHUNK_TYPE).ok_or(NonExistantChunk)?;
+        let mut data = self.chunks[index].data().to_vec();
+        let removed = exif::erase_gps(&mut data)?;
+        if removed > 0 {
+            self.chunks[index] = Chunk::try_new(*self.chunks[index].chunk_type(), data)?;
+        }
+        Ok(removed)
+    }
+
+    /// Inflates and unfilters the data of the IDAT chunks into pixels.
+    pub fn decode_pixels(&self) -> Result<Image> {
+        let idat: Vec<u8> = self.chunks_by_type("IDAT").iter().flat_map(|chunk| chunk.data()).copied().collect();
+        Image::decode(&self.ihdr()?, &idat)
+    }
+
+    /// Every tEXt, zTXt and iTXt entry in file order, each with its own result so that a
+    /// malformed one doesn't hide the others.
+    pub fn texts(&self) -> Vec<Result<TextChunk>> {
+        self.chunks.iter()
+            .filter(|chunk| TextChunk::is_text_chunk_type(chunk.chunk_type()))
+            .map(TextChunk::try_from)
+            .collect()
+    }
+
+    /// Stores `text`, replacing the entries with the same keyword or adding it before IEND.
+    pub fn set_text(&mut self, text: &TextChunk) -> Result<()> {
+        let chunk = text.to_chunk()?;
+        let indices = self.text_indices(text.keyword());
+        match indices.first() {
+            Some(&first) => {
+                self.chunks[first] = chunk;
+                for &index in indices[1..].iter().rev() {
+                    self.chunks.remove(index);
+                }
+            },
+            None => self.insert_before_iend(chunk),
+        }
+        Ok(())
+    }
+
+    /// Removes the entries with `keyword`, malformed or not, and returns their chunks.
+    pub fn remove_texts(&mut self, keyword: &str) -> Result<Vec<Chunk>> {
+        let indices = self.text_indices(keyword);
+        if indices.is_empty() {
+            return Err(NonExistantChunk.into());
+        }
+        let mut removed: Vec<Chunk> = indices.iter().rev().map(|&index| self.chunks.remove(index)).collect();
+        removed.reverse();
+        Ok(removed)
+    }
+
+    fn text_indices(&self, keyword: &str) -> Vec<usize> {
+        self.chunks.iter()
+            .enumerate()
+            .filter(|(_, chunk)| TextChunk::is_text_chunk_type(chunk.chunk_type()))
+            .filter(|(_, chunk)| TextChunk::has_keyword(chunk, keyword))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// The time of the last modification, or `None` if there is no tIME chunk.
+    pub fn time(&self) -> Result<Option<ImageTime>> {
+        self.chunk_by_type(TIME_CHUNK_TYPE)
+            .map(|chunk| ImageTime::from_data(chunk.data()))
+            .transpose()
+    }
+
+    /// Replaces the tIME chunk, or adds one before IEND.
+    pub fn set_time(&mut self, time: &ImageTime) -> Result<()> {
+        let chunk = time.to_chunk()?;
+        match self.position(TIME_CHUNK_TYPE) {
+            Some(index) => self.chunks[index] = chunk,
+            None => self.insert_before_iend(chunk),
+        }
+        Ok(())
+    }
+
+    /// Replaces the chunk of the same type, or inserts `chunk` before the first of the `successors`
+    /// found, at the end if there is none.
+    fn replace_or_insert_before(&mut self, chunk: Chunk, successors: &[&str]) {
+        match self.position(&chunk.chunk_type().to_string()) {
+            Some(index) => self.chunks[index] = chunk,
+            None => {
+                let index = successors.iter()
+                    .filter_map(|chunk_type| self.position(chunk_type))
+                    .min()
+                    .unwrap_or(self.chunks.len());
+                self.chunks.insert(index, chunk);
+            },
+        }
+    }
+
+    fn position(&self, chunk_type: &str) -> Option<usize> {
+        self.chunks.iter().position(|chunk| chunk.chunk_type().to_string().as_str() == chunk_type)
+    }
+
+    pub fn chunks_by_type(&self, chunk_type: &str) -> Vec<&Chunk> {
+        self.chunks.iter().filter(|&chunk| chunk.chunk_type().to_string().as_str() == chunk_type).collect()
+    }
+
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<W> {
+        let mut writer = PngWriter::new(writer)?;
+        writer.write_chunks(&self.chunks)?;
+        writer.finish()
+    }
+
     pub fn as_bytes(&self) -> Vec<u8> {
         let mut bytes = Self::STANDARD_HEADER.to_vec();
         for chunk in &self.chunks {
@@ -54,27 +409,7 @@ impl TryFrom<&[u8]> for Png {
     type Error = Error;
 
     fn try_from(value: &[u8]) -> Result<Self> {
-        let (header, rest) = value.split_at(8);
-        if header != Self::STANDARD_HEADER {
-            return Err(Box::new(IncorrectHeader));
-        }
-        let mut bytes = rest.to_vec();
-        let mut chunks = Vec::new();
-        while !bytes.is_empty() {
-            let chunky = Chunk::try_from(bytes.as_slice());
-            let length;
-            match chunky {
-                Ok(chunky) => {
-                    length = chunky.length() + 12;
-                    chunks.push(chunky);
-                },
-                Err(e) => return Err(e),
-            }
-            bytes.drain(0..length as usize);
-        }
-        Ok(Self {
-            chunks
-        })
+        PngRef::try_from(value)?.to_png()
     }
 }
 
@@ -87,7 +422,9 @@ impl Display for Png {
 #[derive(Debug)]
 pub enum PngError {
     IncorrectHeader,
-    NonExistantChunk
+    NonExistantChunk,
+    IndexOutOfBounds(usize),
+    InvalidStructure(Vec<Violation>)
 }
 
 impl Display for PngError {
@@ -95,6 +432,23 @@ impl Display for PngError {
         match self {
             IncorrectHeader => write!(f ,"An incorrect header was found!"),
             NonExistantChunk => write!(f, "The requested chunk doesn't exist!"),
+            IndexOutOfBounds(index) => write!(f, "There is no position {} to insert a chunk at!", index),
+            InvalidStructure(violations) => {
+                let violations: Vec<String> = violations.iter().map(|violation| violation.to_string()).collect();
+                write!(f, "The png doesn't follow the specification: {}", violations.join(", "))
+            },
+        }
+    }
+}
+
+impl PngError {
+    /// A stable identifier for the error, for machine-readable output.
+    pub fn code(&self) -> &'static str {
+        match self {
+            IncorrectHeader => "incorrect_header",
+            NonExistantChunk => "nonexistent_chunk",
+            IndexOutOfBounds(_) => "index_out_of_bounds",
+            InvalidStructure(_) => "invalid_structure",
         }
     }
 }
@@ -105,7 +459,8 @@ impl std::error::Error for PngError {}
 mod tests {
     use super::*;
     use crate::chunk_type::ChunkType;
-    use crate::chunk::Chunk;
+    use crate::chunk::{Chunk, ChunkError};
+    use crate::PngyinxError;
     use std::str::FromStr;
     use std::convert::TryFrom;
 
@@ -196,6 +551,23 @@ mod tests {
     }
 
 
+    #[test]
+    fn test_truncated_input() {
+        assert!(Png::try_from(&PNG_FILE[..4]).is_err());
+        for end in [20, 100, PNG_FILE.len() - 1] {
+            assert!(Png::try_from(&PNG_FILE[..end]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_invalid_chunk_offset() {
+        let mut bytes = PNG_FILE.to_vec();
+        bytes[41] ^= 0xff;
+        let png = Png::try_from(bytes.as_ref());
+        assert!(matches!(png, Err(PngyinxError::Chunk(ChunkError::InvalidChecksum(33, _, _)))));
+    }
+
+
     #[test]
     fn test_list_chunks() {
         let png = testing_png();
@@ -221,6 +593,35 @@ mod tests {
         assert_eq!(&chunk.data_as_string().unwrap(), "Message");
     }
 
+    #[test]
+    fn test_insert_at() {
+        let mut png = testing_png();
+        png.insert_at(1, chunk_from_strings("TeSt", "Message").unwrap()).unwrap();
+        assert_eq!(&png.chunks()[1].chunk_type().to_string(), "TeSt");
+        assert!(png.insert_at(5, chunk_from_strings("TeSt", "Message").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_insert_after() {
+        let mut png = testing_png();
+        png.insert_after("miDl", chunk_from_strings("TeSt", "Message").unwrap()).unwrap();
+        assert_eq!(&png.chunks()[2].chunk_type().to_string(), "TeSt");
+        assert!(png.insert_after("NoNe", chunk_from_strings("TeSt", "Message").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_insert_before_iend() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.insert_before_iend(chunk_from_strings("teSt", "Message").unwrap());
+        let chunks = png.chunks();
+        assert_eq!(&chunks[chunks.len() - 2].chunk_type().to_string(), "teSt");
+        assert!(png.validate().is_empty());
+
+        let mut png = testing_png();
+        png.insert_before_iend(chunk_from_strings("TeSt", "Message").unwrap());
+        assert_eq!(&png.chunks()[3].chunk_type().to_string(), "TeSt");
+    }
+
     #[test]
     fn test_remove_chunk() {
         let mut png = testing_png();
@@ -230,12 +631,50 @@ mod tests {
         assert!(chunk.is_none());
     }
 
+    #[test]
+    fn test_chunks_by_type() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("TeSt", "First").unwrap());
+        png.append_chunk(chunk_from_strings("TeSt", "Second").unwrap());
+        let chunks: Vec<String> = png.chunks_by_type("TeSt").iter().map(|chunk| chunk.data_as_string().unwrap()).collect();
+        assert_eq!(chunks, vec!["First", "Second"]);
+        assert!(png.chunks_by_type("NoPe").is_empty());
+    }
+
+    #[test]
+    fn test_remove_chunks_by_type() {
+        let mut png = testing_png();
+        let length = png.chunks().len();
+        png.append_chunk(chunk_from_strings("TeSt", "First").unwrap());
+        png.append_chunk(chunk_from_strings("TeSt", "Second").unwrap());
+        let removed = png.remove_chunks_by_type("TeSt").unwrap();
+        assert_eq!(removed.len(), 2);
+        assert_eq!(&removed[1].data_as_string().unwrap(), "Second");
+        assert_eq!(png.chunks().len(), length);
+        assert!(png.remove_chunks_by_type("TeSt").is_err());
+    }
+
     #[test]
     fn test_png_from_image_file() {
         let png = Png::try_from(&PNG_FILE[..]);
         assert!(png.is_ok());
     }
 
+    #[test]
+    fn test_png_from_reader() {
+        let png = Png::from_reader(&PNG_FILE[..]).unwrap();
+        assert_eq!(png.as_bytes(), PNG_FILE.to_vec());
+    }
+
+    #[test]
+    fn test_strict_from_bytes() {
+        assert!(Png::try_from_strict(&PNG_FILE[..]).is_ok());
+
+        let bytes = testing_png().as_bytes();
+        let png = Png::try_from_strict(bytes.as_ref());
+        assert!(matches!(png, Err(PngyinxError::Png(InvalidStructure(_)))));
+    }
+
     #[test]
     fn test_as_bytes() {
         let png = Png::try_from(&PNG_FILE[..]).unwrap();
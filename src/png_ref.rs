@@ -0,0 +1,109 @@
+use crate::chunk_ref::ChunkRef;
+use crate::png::Png;
+use crate::png::PngError::IncorrectHeader;
+use crate::Error;
+use crate::Result;
+
+/// A png borrowed from a byte slice, whose chunks are parsed lazily as `ChunkRef`s.
+#[derive(Clone, Copy, Debug)]
+pub struct PngRef<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> TryFrom<&'a [u8]> for PngRef<'a> {
+    type Error = Error;
+
+    fn try_from(value: &'a [u8]) -> Result<Self> {
+        if value.len() < Png::STANDARD_HEADER.len() || value[..8] != Png::STANDARD_HEADER {
+            return Err(IncorrectHeader.into());
+        }
+        Ok(Self {
+            bytes: value
+        })
+    }
+}
+
+impl<'a> PngRef<'a> {
+    pub fn chunks(&self) -> ChunkRefs<'a> {
+        ChunkRefs {
+            bytes: self.bytes,
+            offset: Png::STANDARD_HEADER.len(),
+        }
+    }
+
+    pub fn chunk_by_type(&self, chunk_type: &str) -> Result<Option<ChunkRef<'a>>> {
+        for chunk in self.chunks() {
+            let chunk = chunk?;
+            if chunk.chunk_type().bytes() == chunk_type.as_bytes() {
+                return Ok(Some(chunk));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn to_png(&self) -> Result<Png> {
+        let chunks = self.chunks()
+            .map(|chunk| chunk.map(|chunk| chunk.to_chunk()))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Png::from_chunks(chunks))
+    }
+}
+
+/// Iterator over the chunks of a `PngRef`. It ends after the first error.
+pub struct ChunkRefs<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Iterator for ChunkRefs<'a> {
+    type Item = Result<ChunkRef<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.bytes.len() {
+            return None;
+        }
+        let chunk = ChunkRef::from_bytes_at(&self.bytes[self.offset..], self.offset);
+        match chunk {
+            Ok(chunk) => self.offset += chunk.as_bytes().len(),
+            Err(_) => self.offset = self.bytes.len(),
+        }
+        Some(chunk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG_FILE: &[u8] = include_bytes!("../pictures/dice.png");
+
+    #[test]
+    fn test_png_ref_chunks() {
+        let png = PngRef::try_from(PNG_FILE).unwrap();
+        let types: Vec<String> = png.chunks()
+            .map(|chunk| chunk.unwrap().chunk_type().to_string())
+            .collect();
+        assert_eq!(types, ["IHDR", "sRGB", "gAMA", "pHYs", "IDAT", "RuSt", "IEND"]);
+    }
+
+    #[test]
+    fn test_png_ref_chunk_by_type() {
+        let png = PngRef::try_from(PNG_FILE).unwrap();
+        let chunk = png.chunk_by_type("RuSt").unwrap().unwrap();
+        assert_eq!(chunk.data(), b"hey");
+        assert!(png.chunk_by_type("miSs").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_png_ref_to_png() {
+        let png = PngRef::try_from(PNG_FILE).unwrap().to_png().unwrap();
+        assert_eq!(png.as_bytes(), PNG_FILE);
+    }
+
+    #[test]
+    fn test_png_ref_truncated() {
+        let png = PngRef::try_from(&PNG_FILE[..100]).unwrap();
+        assert!(png.chunks().any(|chunk| chunk.is_err()));
+        assert!(PngRef::try_from(&PNG_FILE[..4]).is_err());
+    }
+}
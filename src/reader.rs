@@ -0,0 +1,151 @@
+use std::io::{self, Read};
+use crate::chunk::Chunk;
+use crate::chunk::ChunkError::{DataPastEnd, InvalidChecksum, InvalidChunkType, LengthTooLarge, TruncatedCrc, TruncatedLength, TruncatedType};
+use crate::chunk_type::ChunkType;
+use crate::png::Png;
+use crate::png::PngError::IncorrectHeader;
+use crate::Result;
+
+/// Reads a png chunk by chunk from any `Read` source.
+///
+/// The signature is checked by `new`, then every call to `next` reads exactly one chunk,
+/// so a caller looking for a single chunk can stop without consuming the rest of the file.
+/// The iterator ends after the first error.
+pub struct PngReader<R: Read> {
+    reader: R,
+    offset: usize,
+    done: bool,
+}
+
+impl<R: Read> PngReader<R> {
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut header = [0u8; 8];
+        if read_full(&mut reader, &mut header)? != header.len() || header != Png::STANDARD_HEADER {
+            return Err(IncorrectHeader.into());
+        }
+        Ok(Self {
+            reader,
+            offset: header.len(),
+            done: false,
+        })
+    }
+
+    /// The absolute offset of the next chunk in the source.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn read_chunk(&mut self) -> Result<Option<Chunk>> {
+        let offset = self.offset;
+        let mut length_bytes = [0u8; 4];
+        match read_full(&mut self.reader, &mut length_bytes)? {
+            0 => return Ok(None),
+            4 => {},
+            _ => return Err(TruncatedLength(offset).into()),
+        }
+        let length = u32::from_be_bytes(length_bytes);
+        if length > Chunk::MAX_LENGTH {
+            return Err(LengthTooLarge(offset, length).into());
+        }
+        let mut chunk_type_bytes = [0u8; 4];
+        if read_full(&mut self.reader, &mut chunk_type_bytes)? != 4 {
+            return Err(TruncatedType(offset).into());
+        }
+        let chunk_type = ChunkType::try_from(chunk_type_bytes)
+            .map_err(|_| InvalidChunkType(offset, chunk_type_bytes))?;
+        let mut data = Vec::new();
+        let read = (&mut self.reader).take(length as u64).read_to_end(&mut data)?;
+        if read != length as usize {
+            return Err(DataPastEnd(offset, length, read).into());
+        }
+        let mut crc_bytes = [0u8; 4];
+        if read_full(&mut self.reader, &mut crc_bytes)? != 4 {
+            return Err(TruncatedCrc(offset).into());
+        }
+        let crc = u32::from_be_bytes(crc_bytes);
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(&chunk_type_bytes);
+        hasher.update(&data);
+        let checksum = hasher.finalize();
+        if crc != checksum {
+            return Err(InvalidChecksum(offset, crc, checksum).into());
+        }
+        self.offset += length as usize + 12;
+        Ok(Some(Chunk::from_parts(chunk_type, data, crc)))
+    }
+}
+
+impl<R: Read> Iterator for PngReader<R> {
+    type Item = Result<Chunk>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.read_chunk().transpose();
+        if !matches!(result, Some(Ok(_))) {
+            self.done = true;
+        }
+        result
+    }
+}
+
+/// Like `read_exact`, but returns how many bytes were read before the end of the input
+/// instead of failing, so that truncations can be reported with the right error.
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {},
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::ChunkError;
+    use crate::PngyinxError;
+
+    const PNG_FILE: &[u8] = include_bytes!("../pictures/dice.png");
+
+    #[test]
+    fn test_read_all_chunks() {
+        let reader = PngReader::new(PNG_FILE).unwrap();
+        let chunks: Vec<Chunk> = reader.collect::<Result<_>>().unwrap();
+        let png = Png::try_from(PNG_FILE).unwrap();
+        assert_eq!(chunks.len(), png.chunks().len());
+        assert_eq!(chunks.last().unwrap().chunk_type().to_string(), "IEND");
+    }
+
+    #[test]
+    fn test_stop_early() {
+        let mut reader = PngReader::new(PNG_FILE).unwrap();
+        let ihdr = reader.next().unwrap().unwrap();
+        assert_eq!(ihdr.chunk_type().to_string(), "IHDR");
+        assert_eq!(reader.offset(), 33);
+    }
+
+    #[test]
+    fn test_invalid_signature() {
+        let reader = PngReader::new(&PNG_FILE[1..]);
+        assert!(matches!(reader, Err(PngyinxError::Png(IncorrectHeader))));
+    }
+
+    #[test]
+    fn test_truncated_stream() {
+        let mut reader = PngReader::new(&PNG_FILE[..41]).unwrap();
+        assert!(reader.next().unwrap().is_ok());
+        let error = reader.next().unwrap();
+        assert!(matches!(error, Err(PngyinxError::Chunk(ChunkError::DataPastEnd(33, 1, 0)))));
+        assert!(reader.next().is_none());
+    }
+}
@@ -0,0 +1,306 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rand_core::OsRng;
+use sha2::{Digest, Sha256};
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::png::Png;
+use crate::signature::ChunkChange::{Added, Modified, Removed};
+use crate::signature::SignatureError::{InvalidKey, InvalidSignature, MalformedSignature, MissingSignature, UnsupportedVersion, WrongKey};
+use crate::Result;
+
+/// Private, ancillary and unsafe to copy: editors that don't know it must drop it.
+pub const SIGNATURE_CHUNK_TYPE: &str = "pySG";
+pub const VERSION: u8 = 1;
+
+const DOMAIN: &[u8] = b"pngyinx signature v1";
+const KEY_LENGTH: usize = 32;
+const SIGNATURE_LENGTH: usize = 64;
+const ENTRY_LENGTH: usize = 4 + 32;
+const HEADER_LENGTH: usize = 1 + KEY_LENGTH + SIGNATURE_LENGTH;
+
+/// A change between the chunks that were signed and the chunks of the file now.
+/// Indices ignore the signature chunk itself: `Added` and `Modified` hold an index among the
+/// current chunks, `Removed` an index among the signed ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChunkChange {
+    Added(usize, ChunkType),
+    Removed(usize, ChunkType),
+    Modified(usize, ChunkType),
+}
+
+impl Display for ChunkChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Added(index, chunk_type) => write!(f, "Chunk {} ({}) was added", index, chunk_type),
+            Removed(index, chunk_type) => write!(f, "Chunk {} ({}) was removed", index, chunk_type),
+            Modified(index, chunk_type) => write!(f, "Chunk {} ({}) was modified", index, chunk_type),
+        }
+    }
+}
+
+/// Serialized as `{"change", "index", "chunk_type"}`, `change` being `added`, `removed` or `modified`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for ChunkChange {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let (change, index, chunk_type) = match self {
+            Added(index, chunk_type) => ("added", index, chunk_type),
+            Removed(index, chunk_type) => ("removed", index, chunk_type),
+            Modified(index, chunk_type) => ("modified", index, chunk_type),
+        };
+        let mut state = serializer.serialize_struct("ChunkChange", 3)?;
+        state.serialize_field("change", change)?;
+        state.serialize_field("index", index)?;
+        state.serialize_field("chunk_type", chunk_type)?;
+        state.end()
+    }
+}
+
+/// Generates an Ed25519 keypair, returned as `(secret, public)`.
+pub fn generate_signing_keypair() -> ([u8; KEY_LENGTH], [u8; KEY_LENGTH]) {
+    let signing_key = SigningKey::generate(&mut OsRng);
+    (signing_key.to_bytes(), signing_key.verifying_key().to_bytes())
+}
+
+/// Signs every chunk of `png` and stores the signature in a `pySG` chunk before IEND,
+/// replacing any previous signature.
+///
+/// What gets signed is a manifest listing the type and SHA-256 of each chunk in order, so that
+/// `verify` can tell which chunks changed afterwards.
+pub fn sign(png: &mut Png, signing_key: &[u8; KEY_LENGTH]) -> Result<()> {
+    while png.remove_chunk(SIGNATURE_CHUNK_TYPE).is_ok() {}
+    let signing_key = SigningKey::from_bytes(signing_key);
+    let manifest = manifest(png.chunks());
+    let signature = signing_key.sign(&signed_message(&manifest));
+
+    let mut data = Vec::with_capacity(HEADER_LENGTH + manifest.len());
+    data.push(VERSION);
+    data.extend_from_slice(signing_key.verifying_key().as_bytes());
+    data.extend_from_slice(&signature.to_bytes());
+    data.extend(manifest);
+    png.insert_before_iend(Chunk::new(ChunkType::from_str(SIGNATURE_CHUNK_TYPE)?, data));
+    Ok(())
+}
+
+/// Checks the signature of `png` against `public_key` and lists the chunks changed since signing.
+pub fn verify(png: &Png, public_key: &[u8; KEY_LENGTH]) -> Result<Vec<ChunkChange>> {
+    let chunk = png.chunk_by_type(SIGNATURE_CHUNK_TYPE).ok_or(MissingSignature)?;
+    let data = chunk.data();
+    if data.len() < HEADER_LENGTH || !(data.len() - HEADER_LENGTH).is_multiple_of(ENTRY_LENGTH) {
+        return Err(MalformedSignature.into());
+    }
+    if data[0] != VERSION {
+        return Err(UnsupportedVersion(data[0]).into());
+    }
+    if data[1..1 + KEY_LENGTH] != public_key[..] {
+        return Err(WrongKey.into());
+    }
+    let verifying_key = VerifyingKey::from_bytes(public_key).map_err(|_| InvalidKey)?;
+    let signature = Signature::from_bytes(data[1 + KEY_LENGTH..HEADER_LENGTH].try_into().unwrap());
+    let signed_manifest = &data[HEADER_LENGTH..];
+    verifying_key.verify(&signed_message(signed_manifest), &signature).map_err(|_| InvalidSignature)?;
+
+    diff(signed_manifest, &manifest(png.chunks()))
+}
+
+fn signed_message(manifest: &[u8]) -> Vec<u8> {
+    [DOMAIN, manifest].concat()
+}
+
+fn manifest(chunks: &[Chunk]) -> Vec<u8> {
+    chunks.iter()
+        .filter(|chunk| chunk.chunk_type().bytes() != SIGNATURE_CHUNK_TYPE.as_bytes())
+        .flat_map(|chunk| {
+            let mut hasher = Sha256::new();
+            hasher.update(chunk.length().to_be_bytes());
+            hasher.update(chunk.chunk_type().bytes());
+            hasher.update(chunk.data());
+            hasher.update(chunk.crc().to_be_bytes());
+            let mut entry = chunk.chunk_type().bytes().to_vec();
+            entry.extend_from_slice(&hasher.finalize());
+            entry
+        })
+        .collect()
+}
+
+/// Pairs up identical entries first, then entries of the same type in order as modifications;
+/// whatever is left was removed from the signed manifest or added to the current one.
+fn diff(signed: &[u8], current: &[u8]) -> Result<Vec<ChunkChange>> {
+    let signed: Vec<&[u8]> = signed.chunks(ENTRY_LENGTH).collect();
+    let current: Vec<&[u8]> = current.chunks(ENTRY_LENGTH).collect();
+    let mut signed_matched = vec![false; signed.len()];
+    let mut current_matched = vec![false; current.len()];
+    let mut changes = Vec::new();
+
+    for (index, entry) in current.iter().enumerate() {
+        if let Some(found) = (0..signed.len()).find(|&i| !signed_matched[i] && signed[i] == *entry) {
+            signed_matched[found] = true;
+            current_matched[index] = true;
+        }
+    }
+    for (index, entry) in current.iter().enumerate() {
+        if current_matched[index] {
+            continue;
+        }
+        if let Some(found) = (0..signed.len()).find(|&i| !signed_matched[i] && signed[i][..4] == entry[..4]) {
+            signed_matched[found] = true;
+            current_matched[index] = true;
+            changes.push(Modified(index, entry_type(entry)?));
+        }
+    }
+    for (index, entry) in signed.iter().enumerate() {
+        if !signed_matched[index] {
+            changes.push(Removed(index, entry_type(entry)?));
+        }
+    }
+    for (index, entry) in current.iter().enumerate() {
+        if !current_matched[index] {
+            changes.push(Added(index, entry_type(entry)?));
+        }
+    }
+    Ok(changes)
+}
+
+/// The type of a manifest entry. The signed manifest comes from the file, so it may hold
+/// anything its signer put there.
+fn entry_type(entry: &[u8]) -> Result<ChunkType> {
+    let bytes: [u8; 4] = entry[..4].try_into().unwrap();
+    ChunkType::try_from(bytes).map_err(|_| MalformedSignature.into())
+}
+
+#[derive(Debug)]
+pub enum SignatureError {
+    MissingSignature,
+    MalformedSignature,
+    UnsupportedVersion(u8),
+    InvalidKey,
+    WrongKey,
+    InvalidSignature,
+}
+
+impl Display for SignatureError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MissingSignature => write!(f, "The png isn't signed"),
+            MalformedSignature => write!(f, "The signature chunk is malformed"),
+            UnsupportedVersion(version) => write!(f, "Unsupported signature version: {}", version),
+            InvalidKey => write!(f, "The public key isn't a valid Ed25519 key"),
+            WrongKey => write!(f, "The png was signed with another key"),
+            InvalidSignature => write!(f, "The signature doesn't match, the png or its signature was forged"),
+        }
+    }
+}
+
+impl SignatureError {
+    /// A stable identifier for the error, for machine-readable output.
+    pub fn code(&self) -> &'static str {
+        match self {
+            MissingSignature => "missing_signature",
+            MalformedSignature => "malformed_signature",
+            UnsupportedVersion(_) => "unsupported_version",
+            InvalidKey => "invalid_key",
+            WrongKey => "wrong_key",
+            InvalidSignature => "invalid_signature",
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PngyinxError;
+
+    const PNG_FILE: &[u8] = include_bytes!("../pictures/dice.png");
+
+    fn signed_png() -> (Png, [u8; KEY_LENGTH]) {
+        let (secret, public) = generate_signing_keypair();
+        let mut png = Png::try_from(PNG_FILE).unwrap();
+        sign(&mut png, &secret).unwrap();
+        (png, public)
+    }
+
+    fn chunk(chunk_type: &str, data: &str) -> Chunk {
+        Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data.as_bytes().to_vec())
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let (png, public) = signed_png();
+        assert!(png.validate().is_empty());
+        let png = Png::try_from(png.as_bytes().as_slice()).unwrap();
+        assert_eq!(verify(&png, &public).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_resign_replaces_signature() {
+        let (secret, public) = generate_signing_keypair();
+        let mut png = Png::try_from(PNG_FILE).unwrap();
+        sign(&mut png, &secret).unwrap();
+        sign(&mut png, &secret).unwrap();
+        let signatures = png.chunks().iter().filter(|chunk| chunk.chunk_type().to_string() == SIGNATURE_CHUNK_TYPE).count();
+        assert_eq!(signatures, 1);
+        assert_eq!(verify(&png, &public).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_detect_changes() {
+        let (mut png, public) = signed_png();
+        png.remove_chunk("gAMA").unwrap();
+        png.remove_chunk("RuSt").unwrap();
+        png.insert_after("IDAT", chunk("RuSt", "changed")).unwrap();
+        png.insert_before_iend(chunk("teSt", "new"));
+
+        let changes = verify(&png, &public).unwrap();
+        assert_eq!(changes, vec![
+            Modified(4, ChunkType::from_str("RuSt").unwrap()),
+            Removed(2, ChunkType::from_str("gAMA").unwrap()),
+            Added(5, ChunkType::from_str("teSt").unwrap()),
+        ]);
+    }
+
+    #[test]
+    fn test_wrong_key() {
+        let (png, _) = signed_png();
+        let (_, other) = generate_signing_keypair();
+        assert!(matches!(verify(&png, &other), Err(PngyinxError::Signature(WrongKey))));
+    }
+
+    #[test]
+    fn test_forged_manifest() {
+        let (png, public) = signed_png();
+        let mut chunks = png.chunks().to_vec();
+        let index = chunks.iter().position(|chunk| chunk.chunk_type().to_string() == SIGNATURE_CHUNK_TYPE).unwrap();
+        let mut data = chunks[index].data().to_vec();
+        let last = data.len() - 1;
+        data[last] ^= 1;
+        chunks[index] = Chunk::new(ChunkType::from_str(SIGNATURE_CHUNK_TYPE).unwrap(), data);
+        let png = Png::from_chunks(chunks);
+        assert!(matches!(verify(&png, &public), Err(PngyinxError::Signature(InvalidSignature))));
+    }
+
+    #[test]
+    fn test_invalid_manifest_entry() {
+        let (secret, public) = generate_signing_keypair();
+        let mut png = Png::try_from(PNG_FILE).unwrap();
+        let mut manifest = manifest(png.chunks());
+        manifest[..4].copy_from_slice(&[0, 1, 2, 3]);
+        let signing_key = SigningKey::from_bytes(&secret);
+        let mut data = vec![VERSION];
+        data.extend_from_slice(&public);
+        data.extend_from_slice(&signing_key.sign(&signed_message(&manifest)).to_bytes());
+        data.extend(manifest);
+        png.insert_before_iend(Chunk::new(ChunkType::from_str(SIGNATURE_CHUNK_TYPE).unwrap(), data));
+        assert!(matches!(verify(&png, &public), Err(PngyinxError::Signature(MalformedSignature))));
+    }
+
+    #[test]
+    fn test_unsigned_png() {
+        let png = Png::try_from(PNG_FILE).unwrap();
+        let (_, public) = generate_signing_keypair();
+        assert!(matches!(verify(&png, &public), Err(PngyinxError::Signature(MissingSignature))));
+    }
+}
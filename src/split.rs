@@ -0,0 +1,307 @@
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+use rand_core::{OsRng, RngCore};
+use crate::chunk::Chunk;
+use crate::split::SplitError::{DuplicateFragment, FragmentTooSmall, MissingFragments, MixedPayloads, NotAFragment, OutOfOrder, TotalMismatch, TruncatedHeader, UnsupportedVersion};
+use crate::Result;
+
+/// Every fragment of a split payload starts with these bytes, followed by the format version.
+pub const MAGIC: [u8; 3] = *b"PYS";
+pub const VERSION: u8 = 1;
+pub const HEADER_LENGTH: usize = 4 + 8 + 4 + 4;
+
+/// The largest fragment that still fits in a chunk.
+pub const MAX_FRAGMENT_SIZE: usize = Chunk::MAX_LENGTH as usize;
+
+/// One piece of a payload spread over several chunks of the same type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fragment<'a> {
+    pub id: [u8; 8],
+    pub index: u32,
+    pub total: u32,
+    pub data: &'a [u8],
+}
+
+/// Whether `data` starts with a fragment header.
+pub fn is_fragment(data: &[u8]) -> bool {
+    data.starts_with(&MAGIC)
+}
+
+pub fn parse_fragment(data: &[u8]) -> Result<Fragment<'_>> {
+    if !is_fragment(data) {
+        return Err(NotAFragment.into());
+    }
+    let (header, data) = data.split_at_checked(HEADER_LENGTH).ok_or(TruncatedHeader)?;
+    if header[3] != VERSION {
+        return Err(UnsupportedVersion(header[3]).into());
+    }
+    Ok(Fragment {
+        id: header[4..12].try_into().unwrap(),
+        index: u32::from_be_bytes(header[12..16].try_into().unwrap()),
+        total: u32::from_be_bytes(header[16..20].try_into().unwrap()),
+        data,
+    })
+}
+
+/// Cuts `payload` into fragments of at most `max_size` bytes, headers included, all sharing a random id.
+/// A payload that already fits is returned as is, without a header.
+pub fn split_payload(payload: &[u8], max_size: usize) -> Result<Vec<Vec<u8>>> {
+    let max_size = max_size.min(MAX_FRAGMENT_SIZE);
+    if payload.len() <= max_size {
+        return Ok(vec![payload.to_vec()]);
+    }
+    if max_size <= HEADER_LENGTH {
+        return Err(FragmentTooSmall(max_size).into());
+    }
+    let pieces: Vec<&[u8]> = payload.chunks(max_size - HEADER_LENGTH).collect();
+    let total = u32::try_from(pieces.len()).map_err(|_| FragmentTooSmall(max_size))?;
+    let mut id = [0u8; 8];
+    OsRng.fill_bytes(&mut id);
+    Ok(pieces.iter()
+        .enumerate()
+        .map(|(index, piece)| {
+            let mut fragment = Vec::with_capacity(HEADER_LENGTH + piece.len());
+            fragment.extend_from_slice(&MAGIC);
+            fragment.push(VERSION);
+            fragment.extend_from_slice(&id);
+            fragment.extend_from_slice(&(index as u32).to_be_bytes());
+            fragment.extend_from_slice(&total.to_be_bytes());
+            fragment.extend_from_slice(piece);
+            fragment
+        })
+        .collect())
+}
+
+/// Puts the fragments of one payload back together, given in the order they appear in the file.
+///
+/// Fails if a fragment belongs to another payload, disagrees on the total, is duplicated,
+/// comes out of order, or if some are missing, in which case the missing ranges are reported.
+pub fn reassemble(fragments: &[Fragment]) -> Result<Vec<u8>> {
+    let first = fragments.first().ok_or(MissingFragments(Vec::new(), 0))?;
+    // Sized by the fragments found rather than by the total, which comes from the file.
+    let mut seen = BTreeSet::new();
+    let mut payload = Vec::new();
+    let mut previous = None;
+    for fragment in fragments {
+        if fragment.id != first.id {
+            return Err(MixedPayloads.into());
+        }
+        if fragment.total != first.total || fragment.index >= first.total {
+            return Err(TotalMismatch(first.total, fragment.index, fragment.total).into());
+        }
+        if !seen.insert(fragment.index) {
+            return Err(DuplicateFragment(fragment.index).into());
+        }
+        if previous.is_some_and(|previous| fragment.index < previous) {
+            return Err(OutOfOrder(previous.unwrap(), fragment.index).into());
+        }
+        previous = Some(fragment.index);
+        payload.extend_from_slice(fragment.data);
+    }
+    let missing = missing_ranges(&seen, first.total);
+    if !missing.is_empty() {
+        return Err(MissingFragments(missing, first.total).into());
+    }
+    Ok(payload)
+}
+
+/// The gaps between the indices of `seen`, as inclusive ranges. Walking the fragments found
+/// keeps this bounded by them rather than by the total read from the file.
+fn missing_ranges(seen: &BTreeSet<u32>, total: u32) -> Vec<(u32, u32)> {
+    let mut missing = Vec::new();
+    let mut next = 0u64;
+    for &index in seen.iter().chain([total].iter()) {
+        if index as u64 > next {
+            missing.push((next as u32, index - 1));
+        }
+        next = index as u64 + 1;
+    }
+    missing
+}
+
+/// Gives back every payload stored in `data`, the contents of the chunks of one type in file order.
+///
+/// A chunk that isn't a fragment is a payload on its own, fragments are grouped by id and
+/// reassembled. Payloads come in the order of their first chunk, each with its own result so
+/// that a damaged one doesn't hide the others.
+pub fn join_all<'a>(data: impl IntoIterator<Item = &'a [u8]>) -> Vec<Result<Vec<u8>>> {
+    let mut payloads = Vec::new();
+    for data in data {
+        if !is_fragment(data) {
+            payloads.push(Stored::Raw(data));
+            continue;
+        }
+        let fragment = match parse_fragment(data) {
+            Ok(fragment) => fragment,
+            Err(e) => {
+                payloads.push(Stored::Invalid(e));
+                continue;
+            },
+        };
+        let group = payloads.iter_mut().find_map(|payload| match payload {
+            Stored::Split(fragments) if fragments[0].id == fragment.id => Some(fragments),
+            _ => None,
+        });
+        match group {
+            Some(fragments) => fragments.push(fragment),
+            None => payloads.push(Stored::Split(vec![fragment])),
+        }
+    }
+    payloads.into_iter()
+        .map(|payload| match payload {
+            Stored::Raw(data) => Ok(data.to_vec()),
+            Stored::Split(fragments) => reassemble(&fragments),
+            Stored::Invalid(e) => Err(e),
+        })
+        .collect()
+}
+
+enum Stored<'a> {
+    Raw(&'a [u8]),
+    Split(Vec<Fragment<'a>>),
+    Invalid(crate::Error),
+}
+
+#[derive(Debug)]
+pub enum SplitError {
+    NotAFragment,
+    TruncatedHeader,
+    UnsupportedVersion(u8),
+    FragmentTooSmall(usize),
+    MixedPayloads,
+    TotalMismatch(u32, u32, u32),
+    DuplicateFragment(u32),
+    OutOfOrder(u32, u32),
+    MissingFragments(Vec<(u32, u32)>, u32),
+}
+
+impl Display for SplitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NotAFragment => write!(f, "The chunk isn't part of a split payload"),
+            TruncatedHeader => write!(f, "The fragment header is truncated"),
+            UnsupportedVersion(version) => write!(f, "Unsupported fragment version: {}", version),
+            FragmentTooSmall(size) => write!(f, "Fragments of {} bytes are too small to split the payload", size),
+            MixedPayloads => write!(f, "The fragments belong to different payloads"),
+            TotalMismatch(expected, index, total) => write!(f, "Fragment {} claims {} fragments in total instead of {}", index, total, expected),
+            DuplicateFragment(index) => write!(f, "Fragment {} appears more than once", index),
+            OutOfOrder(previous, index) => write!(f, "Fragment {} comes after fragment {}", index, previous),
+            MissingFragments(missing, total) => {
+                let missing: Vec<String> = missing.iter()
+                    .map(|&(first, last)| if first == last { first.to_string() } else { format!("{}-{}", first, last) })
+                    .collect();
+                write!(f, "Fragments {} of {} are missing", missing.join(", "), total)
+            },
+        }
+    }
+}
+
+impl SplitError {
+    /// A stable identifier for the error, for machine-readable output.
+    pub fn code(&self) -> &'static str {
+        match self {
+            NotAFragment => "not_a_fragment",
+            TruncatedHeader => "truncated_header",
+            UnsupportedVersion(_) => "unsupported_version",
+            FragmentTooSmall(_) => "fragment_too_small",
+            MixedPayloads => "mixed_payloads",
+            TotalMismatch(_, _, _) => "total_mismatch",
+            DuplicateFragment(_) => "duplicate_fragment",
+            OutOfOrder(_, _) => "out_of_order",
+            MissingFragments(_, _) => "missing_fragments",
+        }
+    }
+}
+
+impl std::error::Error for SplitError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PngyinxError;
+
+    fn fragments(payload: &[u8], max_size: usize) -> Vec<Vec<u8>> {
+        split_payload(payload, max_size).unwrap()
+    }
+
+    fn parse(fragments: &[Vec<u8>]) -> Vec<Fragment<'_>> {
+        fragments.iter().map(|fragment| parse_fragment(fragment).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_small_payload_is_not_split() {
+        let fragments = fragments(b"Hello", 100);
+        assert_eq!(fragments, vec![b"Hello".to_vec()]);
+        assert!(!is_fragment(&fragments[0]));
+    }
+
+    #[test]
+    fn test_split_and_reassemble() {
+        let payload: Vec<u8> = (0..1000u32).map(|i| i as u8).collect();
+        let fragments = fragments(&payload, 120);
+        assert_eq!(fragments.len(), 10);
+        assert!(fragments.iter().all(|fragment| fragment.len() <= 120));
+        assert_eq!(reassemble(&parse(&fragments)).unwrap(), payload);
+    }
+
+    #[test]
+    fn test_missing_fragments() {
+        let fragments = fragments(&[7u8; 1000], 120);
+        let mut parsed = parse(&fragments);
+        parsed.remove(7);
+        parsed.remove(2);
+        let result = reassemble(&parsed);
+        assert!(matches!(result, Err(PngyinxError::Split(MissingFragments(ref missing, 10))) if missing == &vec![(2, 2), (7, 7)]));
+        assert_eq!(result.unwrap_err().to_string(), "Invalid split payload: Fragments 2, 7 of 10 are missing");
+    }
+
+    #[test]
+    fn test_huge_total() {
+        let mut fragment = fragments(&[7u8; 300], 120).remove(0);
+        fragment[16..20].copy_from_slice(&u32::MAX.to_be_bytes());
+        let parsed = [parse_fragment(&fragment).unwrap()];
+        assert!(matches!(reassemble(&parsed), Err(PngyinxError::Split(MissingFragments(ref missing, u32::MAX))) if missing == &vec![(1, u32::MAX - 1)]));
+
+        fragment[3] = 2;
+        assert!(matches!(parse_fragment(&fragment), Err(PngyinxError::Split(UnsupportedVersion(2)))));
+    }
+
+    #[test]
+    fn test_duplicate_and_out_of_order() {
+        let fragments = fragments(&[7u8; 300], 120);
+        let mut parsed = parse(&fragments);
+        parsed.push(parsed[1]);
+        assert!(matches!(reassemble(&parsed), Err(PngyinxError::Split(DuplicateFragment(1)))));
+
+        let mut parsed = parse(&fragments);
+        parsed.swap(0, 2);
+        assert!(matches!(reassemble(&parsed), Err(PngyinxError::Split(OutOfOrder(2, 1)))));
+    }
+
+    #[test]
+    fn test_mixed_payloads() {
+        let first = fragments(&[1u8; 300], 120);
+        let second = fragments(&[2u8; 300], 120);
+        let mut parsed = parse(&first);
+        parsed[1] = parse_fragment(&second[1]).unwrap();
+        assert!(matches!(reassemble(&parsed), Err(PngyinxError::Split(MixedPayloads))));
+    }
+
+    #[test]
+    fn test_join_all() {
+        let payload = [3u8; 300];
+        let ours = fragments(&payload, 120);
+        let other = fragments(&[4u8; 300], 120);
+        let data = [&ours[0][..], b"raw", &other[0], &ours[1], &ours[2]];
+        let payloads = join_all(data);
+        assert_eq!(payloads.len(), 3);
+        assert_eq!(payloads[0].as_ref().unwrap(), &payload);
+        assert_eq!(payloads[1].as_ref().unwrap(), b"raw");
+        assert!(matches!(payloads[2], Err(PngyinxError::Split(MissingFragments(_, _)))));
+    }
+
+    #[test]
+    fn test_fragment_too_small() {
+        assert!(matches!(split_payload(&[0u8; 100], HEADER_LENGTH), Err(PngyinxError::Split(FragmentTooSmall(_)))));
+    }
+}
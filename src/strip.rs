@@ -0,0 +1,129 @@
+use std::fmt::{Display, Formatter};
+use crate::chunk_type::ChunkType;
+use crate::strip::StripError::CriticalChunk;
+use crate::Result;
+
+/// The chunks that can tell who made an image, where or when.
+pub const PRIVACY_CHUNKS: [&str; 5] = ["eXIf", "tEXt", "iTXt", "zTXt", "tIME"];
+
+/// A ready-made set of chunks to strip.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "snake_case"))]
+pub enum Preset {
+    /// Every ancillary chunk.
+    Ancillary,
+    /// The chunks of `PRIVACY_CHUNKS`.
+    Privacy,
+    /// Every private chunk, critical or not, as no public decoder needs them.
+    Private,
+}
+
+impl Preset {
+    pub fn matches(&self, chunk_type: &ChunkType) -> bool {
+        match self {
+            Preset::Ancillary => !chunk_type.is_critical(),
+            Preset::Privacy => PRIVACY_CHUNKS.contains(&chunk_type.to_string().as_str()),
+            Preset::Private => !chunk_type.is_public(),
+        }
+    }
+}
+
+/// Which chunks to strip: those of the preset and of `drop`, except those of `keep`. Public
+/// critical chunks, which the image can't do without, are never stripped.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StripPolicy {
+    pub preset: Option<Preset>,
+    pub keep: Vec<ChunkType>,
+    pub drop: Vec<ChunkType>,
+}
+
+impl StripPolicy {
+    /// Checks that `drop` doesn't name a public critical chunk.
+    pub fn check(&self) -> Result<()> {
+        match self.drop.iter().find(|chunk_type| is_required(chunk_type)) {
+            Some(chunk_type) => Err(CriticalChunk(*chunk_type).into()),
+            None => Ok(()),
+        }
+    }
+
+    pub fn strips(&self, chunk_type: &ChunkType) -> bool {
+        if is_required(chunk_type) || self.keep.contains(chunk_type) {
+            return false;
+        }
+        self.drop.contains(chunk_type) || self.preset.is_some_and(|preset| preset.matches(chunk_type))
+    }
+}
+
+fn is_required(chunk_type: &ChunkType) -> bool {
+    chunk_type.is_critical() && chunk_type.is_public()
+}
+
+#[derive(Debug)]
+pub enum StripError {
+    CriticalChunk(ChunkType),
+}
+
+impl Display for StripError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CriticalChunk(chunk_type) => write!(f, "{} is critical and can't be dropped", chunk_type),
+        }
+    }
+}
+
+impl StripError {
+    /// A stable identifier for the error, for machine-readable output.
+    pub fn code(&self) -> &'static str {
+        match self {
+            CriticalChunk(_) => "critical_chunk",
+        }
+    }
+}
+
+impl std::error::Error for StripError {}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use super::*;
+    use crate::png::Png;
+    use crate::PngyinxError;
+
+    const PNG_FILE: &[u8] = include_bytes!("../pictures/dice.png");
+
+    fn types(png: &Png) -> Vec<String> {
+        png.chunks().iter().map(|chunk| chunk.chunk_type().to_string()).collect()
+    }
+
+    fn chunk_type(chunk_type: &str) -> ChunkType {
+        ChunkType::from_str(chunk_type).unwrap()
+    }
+
+    #[test]
+    fn test_presets() {
+        let mut png = Png::try_from(PNG_FILE).unwrap();
+        let removed = png.strip(&StripPolicy { preset: Some(Preset::Private), ..Default::default() }).unwrap();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(types(&png), vec!["IHDR", "sRGB", "gAMA", "pHYs", "IDAT", "IEND"]);
+
+        let policy = StripPolicy { preset: Some(Preset::Ancillary), keep: vec![chunk_type("pHYs")], ..Default::default() };
+        let removed = png.strip(&policy).unwrap();
+        assert_eq!(removed.iter().map(|chunk| chunk.as_bytes().len()).sum::<usize>(), 13 + 16);
+        assert_eq!(types(&png), vec!["IHDR", "pHYs", "IDAT", "IEND"]);
+        assert!(png.validate().is_empty());
+    }
+
+    #[test]
+    fn test_privacy_and_drop() {
+        let policy = StripPolicy { preset: Some(Preset::Privacy), drop: vec![chunk_type("gAMA")], ..Default::default() };
+        assert!(policy.strips(&chunk_type("tIME")));
+        assert!(policy.strips(&chunk_type("gAMA")));
+        assert!(!policy.strips(&chunk_type("sRGB")));
+        assert!(!policy.strips(&chunk_type("IDAT")));
+
+        let policy = StripPolicy { drop: vec![chunk_type("IDAT")], ..Default::default() };
+        let mut png = Png::try_from(PNG_FILE).unwrap();
+        assert!(matches!(png.strip(&policy), Err(PngyinxError::Strip(CriticalChunk(_)))));
+        assert_eq!(png.chunks().len(), 7);
+    }
+}
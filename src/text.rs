@@ -0,0 +1,366 @@
+use std::fmt::{Display, Formatter};
+use std::io::{Read, Write};
+use std::str::FromStr;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::text::TextError::{DecompressionFailed, InvalidKeyword, InvalidUtf8, MissingSeparator, NotLatin1, NotText, UnknownCompressionMethod};
+use crate::Result;
+
+pub const TEXT_CHUNK_TYPE: &str = "tEXt";
+pub const ZTXT_CHUNK_TYPE: &str = "zTXt";
+pub const ITXT_CHUNK_TYPE: &str = "iTXt";
+
+/// The limit on the size of a decompressed text, far above any sensible metadata.
+pub const MAX_TEXT_SIZE: usize = 16 * 1024 * 1024;
+
+/// A keyword and its text, stored in one of the three textual chunks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(tag = "kind", rename_all = "snake_case"))]
+pub enum TextChunk {
+    /// tEXt: Latin-1 text.
+    Text { keyword: String, text: String },
+    /// zTXt: Latin-1 text compressed with zlib.
+    Compressed { keyword: String, text: String },
+    /// iTXt: UTF-8 text with the language it is written in and the keyword translated to it.
+    International {
+        keyword: String,
+        compressed: bool,
+        language: String,
+        translated_keyword: String,
+        text: String,
+    },
+}
+
+impl TryFrom<&Chunk> for TextChunk {
+    type Error = crate::Error;
+
+    fn try_from(value: &Chunk) -> Result<Self> {
+        let data = value.data();
+        match value.chunk_type().to_string().as_str() {
+            TEXT_CHUNK_TYPE => {
+                let (keyword, text) = split_at_null(data)?;
+                Ok(TextChunk::Text { keyword: read_keyword(keyword)?, text: from_latin1(text) })
+            },
+            ZTXT_CHUNK_TYPE => {
+                let (keyword, rest) = split_at_null(data)?;
+                let (&method, text) = rest.split_first().ok_or(MissingSeparator)?;
+                check_compression_method(method)?;
+                Ok(TextChunk::Compressed { keyword: read_keyword(keyword)?, text: from_latin1(&inflate(text)?) })
+            },
+            ITXT_CHUNK_TYPE => {
+                let (keyword, rest) = split_at_null(data)?;
+                let (flag, method, rest) = match rest {
+                    [flag, method, rest @ ..] => (*flag, *method, rest),
+                    _ => return Err(MissingSeparator.into()),
+                };
+                let (language, rest) = split_at_null(rest)?;
+                let (translated_keyword, text) = split_at_null(rest)?;
+                let compressed = flag != 0;
+                let text = if compressed {
+                    check_compression_method(method)?;
+                    inflate(text)?
+                } else {
+                    text.to_vec()
+                };
+                Ok(TextChunk::International {
+                    keyword: read_keyword(keyword)?,
+                    compressed,
+                    language: String::from_utf8(language.to_vec()).map_err(|_| InvalidUtf8)?,
+                    translated_keyword: String::from_utf8(translated_keyword.to_vec()).map_err(|_| InvalidUtf8)?,
+                    text: String::from_utf8(text).map_err(|_| InvalidUtf8)?,
+                })
+            },
+            _ => Err(NotText(*value.chunk_type()).into()),
+        }
+    }
+}
+
+impl Display for TextChunk {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.keyword(), self.text())
+    }
+}
+
+impl TextChunk {
+    /// A tEXt chunk, or an iTXt one if `text` can't be written in Latin-1.
+    pub fn new(keyword: &str, text: &str) -> TextChunk {
+        if to_latin1(text).is_ok() {
+            TextChunk::Text { keyword: keyword.to_string(), text: text.to_string() }
+        } else {
+            TextChunk::international(keyword, text, false)
+        }
+    }
+
+    /// A zTXt chunk, or a compressed iTXt one if `text` can't be written in Latin-1.
+    pub fn compressed(keyword: &str, text: &str) -> TextChunk {
+        if to_latin1(text).is_ok() {
+            TextChunk::Compressed { keyword: keyword.to_string(), text: text.to_string() }
+        } else {
+            TextChunk::international(keyword, text, true)
+        }
+    }
+
+    /// An iTXt chunk without language tag nor translated keyword.
+    pub fn international(keyword: &str, text: &str, compressed: bool) -> TextChunk {
+        TextChunk::International {
+            keyword: keyword.to_string(),
+            compressed,
+            language: String::new(),
+            translated_keyword: String::new(),
+            text: text.to_string(),
+        }
+    }
+
+    /// Whether `chunk_type` is one of the three textual chunk types.
+    pub fn is_text_chunk_type(chunk_type: &ChunkType) -> bool {
+        [TEXT_CHUNK_TYPE, ZTXT_CHUNK_TYPE, ITXT_CHUNK_TYPE].iter().any(|text| text.as_bytes() == chunk_type.bytes())
+    }
+
+    /// Whether the textual `chunk` has `keyword`, read from its raw bytes so that entries too
+    /// malformed to parse can still be found.
+    pub fn has_keyword(chunk: &Chunk, keyword: &str) -> bool {
+        let raw = chunk.data().split(|&byte| byte == 0).next().unwrap_or_default();
+        from_latin1(raw) == keyword
+    }
+
+    pub fn keyword(&self) -> &str {
+        match self {
+            TextChunk::Text { keyword, .. } | TextChunk::Compressed { keyword, .. } | TextChunk::International { keyword, .. } => keyword,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        match self {
+            TextChunk::Text { text, .. } | TextChunk::Compressed { text, .. } | TextChunk::International { text, .. } => text,
+        }
+    }
+
+    pub fn chunk_type(&self) -> &'static str {
+        match self {
+            TextChunk::Text { .. } => TEXT_CHUNK_TYPE,
+            TextChunk::Compressed { .. } => ZTXT_CHUNK_TYPE,
+            TextChunk::International { .. } => ITXT_CHUNK_TYPE,
+        }
+    }
+
+    /// Builds the chunk after checking the keyword, and that tEXt and zTXt texts are Latin-1.
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        let mut data = write_keyword(self.keyword())?;
+        data.push(0);
+        match self {
+            TextChunk::Text { text, .. } => data.extend(to_latin1(text)?),
+            TextChunk::Compressed { text, .. } => {
+                data.push(0);
+                data.extend(deflate(&to_latin1(text)?)?);
+            },
+            TextChunk::International { compressed, language, translated_keyword, text, .. } => {
+                data.push(*compressed as u8);
+                data.push(0);
+                data.extend_from_slice(language.as_bytes());
+                data.push(0);
+                data.extend_from_slice(translated_keyword.as_bytes());
+                data.push(0);
+                if *compressed {
+                    data.extend(deflate(text.as_bytes())?);
+                } else {
+                    data.extend_from_slice(text.as_bytes());
+                }
+            },
+        }
+        Chunk::try_new(ChunkType::from_str(self.chunk_type())?, data)
+    }
+}
+
+/// Checks a keyword against the specification: 1 to 79 printable Latin-1 characters, without
+/// leading, trailing or consecutive spaces.
+pub fn check_keyword(keyword: &str) -> Result<()> {
+    let length = keyword.chars().count();
+    let printable = keyword.chars().all(|c| matches!(c as u32, 32..=126 | 161..=255));
+    if !(1..=79).contains(&length) || !printable || keyword.starts_with(' ') || keyword.ends_with(' ') || keyword.contains("  ") {
+        return Err(InvalidKeyword(keyword.to_string()).into());
+    }
+    Ok(())
+}
+
+fn read_keyword(bytes: &[u8]) -> Result<String> {
+    let keyword = from_latin1(bytes);
+    check_keyword(&keyword)?;
+    Ok(keyword)
+}
+
+fn write_keyword(keyword: &str) -> Result<Vec<u8>> {
+    check_keyword(keyword)?;
+    to_latin1(keyword)
+}
+
+fn split_at_null(data: &[u8]) -> Result<(&[u8], &[u8])> {
+    let index = data.iter().position(|&byte| byte == 0).ok_or(MissingSeparator)?;
+    Ok((&data[..index], &data[index + 1..]))
+}
+
+fn from_latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|&byte| byte as char).collect()
+}
+
+fn to_latin1(text: &str) -> Result<Vec<u8>> {
+    text.chars()
+        .map(|c| u8::try_from(c).map_err(|_| NotLatin1(c).into()))
+        .collect()
+}
+
+fn check_compression_method(method: u8) -> Result<()> {
+    if method != 0 {
+        return Err(UnknownCompressionMethod(method).into());
+    }
+    Ok(())
+}
+
+fn deflate(data: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+fn inflate(data: &[u8]) -> Result<Vec<u8>> {
+    let mut text = Vec::new();
+    ZlibDecoder::new(data)
+        .take(MAX_TEXT_SIZE as u64 + 1)
+        .read_to_end(&mut text)
+        .map_err(|_| DecompressionFailed)?;
+    if text.len() > MAX_TEXT_SIZE {
+        return Err(DecompressionFailed.into());
+    }
+    Ok(text)
+}
+
+#[derive(Debug)]
+pub enum TextError {
+    NotText(ChunkType),
+    InvalidKeyword(String),
+    NotLatin1(char),
+    MissingSeparator,
+    UnknownCompressionMethod(u8),
+    DecompressionFailed,
+    InvalidUtf8,
+}
+
+impl Display for TextError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NotText(chunk_type) => write!(f, "{} isn't a textual chunk", chunk_type),
+            InvalidKeyword(keyword) => write!(f, "Invalid keyword {:?}: it must be 1 to 79 printable Latin-1 characters without leading, trailing or consecutive spaces", keyword),
+            NotLatin1(c) => write!(f, "The character {:?} can't be written in Latin-1", c),
+            MissingSeparator => write!(f, "The textual chunk is truncated"),
+            UnknownCompressionMethod(method) => write!(f, "Unknown compression method: {}", method),
+            DecompressionFailed => write!(f, "The text couldn't be decompressed"),
+            InvalidUtf8 => write!(f, "The international text isn't valid UTF-8"),
+        }
+    }
+}
+
+impl TextError {
+    /// A stable identifier for the error, for machine-readable output.
+    pub fn code(&self) -> &'static str {
+        match self {
+            NotText(_) => "not_text",
+            InvalidKeyword(_) => "invalid_keyword",
+            NotLatin1(_) => "not_latin1",
+            MissingSeparator => "missing_separator",
+            UnknownCompressionMethod(_) => "unknown_compression_method",
+            DecompressionFailed => "decompression_failed",
+            InvalidUtf8 => "invalid_utf8",
+        }
+    }
+}
+
+impl std::error::Error for TextError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::Png;
+    use crate::PngyinxError;
+
+    const PNG_FILE: &[u8] = include_bytes!("../pictures/dice.png");
+
+    fn round_trip(text: TextChunk) {
+        let chunk = text.to_chunk().unwrap();
+        assert_eq!(chunk.chunk_type().to_string(), text.chunk_type());
+        assert_eq!(TextChunk::try_from(&chunk).unwrap(), text);
+    }
+
+    #[test]
+    fn test_round_trips() {
+        round_trip(TextChunk::new("Title", "Les dés"));
+        round_trip(TextChunk::compressed("Comment", &"A long comment. ".repeat(50)));
+        round_trip(TextChunk::new("Author", "Ὀδυσσεύς"));
+        round_trip(TextChunk::International {
+            keyword: String::from("Title"),
+            compressed: true,
+            language: String::from("el"),
+            translated_keyword: String::from("Τίτλος"),
+            text: String::from("Ζάρια"),
+        });
+    }
+
+    #[test]
+    fn test_text_layout() {
+        let chunk = TextChunk::new("Title", "dé").to_chunk().unwrap();
+        assert_eq!(chunk.data(), b"Title\0d\xe9");
+        assert!(matches!(TextChunk::new("Author", "Ὀ"), TextChunk::International { .. }));
+    }
+
+    #[test]
+    fn test_keywords() {
+        assert!(check_keyword("Creation Time").is_ok());
+        assert!(check_keyword("Café").is_ok());
+        for keyword in ["", " Title", "Title ", "Two  spaces", "Tab\there", "Ὀδυσσεύς", &"k".repeat(80)] {
+            assert!(matches!(check_keyword(keyword), Err(PngyinxError::Text(InvalidKeyword(_)))), "{:?}", keyword);
+        }
+        assert!(TextChunk::new(" bad", "text").to_chunk().is_err());
+    }
+
+    #[test]
+    fn test_invalid_chunks() {
+        let chunk = Chunk::new(ChunkType::from_str("tEXt").unwrap(), b"no separator".to_vec());
+        assert!(matches!(TextChunk::try_from(&chunk), Err(PngyinxError::Text(MissingSeparator))));
+        let chunk = Chunk::new(ChunkType::from_str("zTXt").unwrap(), b"Title\0\x01data".to_vec());
+        assert!(matches!(TextChunk::try_from(&chunk), Err(PngyinxError::Text(UnknownCompressionMethod(1)))));
+        let chunk = Chunk::new(ChunkType::from_str("zTXt").unwrap(), b"Title\0\0garbage".to_vec());
+        assert!(matches!(TextChunk::try_from(&chunk), Err(PngyinxError::Text(DecompressionFailed))));
+        let chunk = Chunk::new(ChunkType::from_str("RuSt").unwrap(), Vec::new());
+        assert!(matches!(TextChunk::try_from(&chunk), Err(PngyinxError::Text(NotText(_)))));
+    }
+
+    #[test]
+    fn test_png_texts() {
+        let mut png = Png::try_from(PNG_FILE).unwrap();
+        assert!(png.texts().is_empty());
+        png.set_text(&TextChunk::new("Title", "Dice")).unwrap();
+        png.set_text(&TextChunk::new("Author", "Me")).unwrap();
+        png.set_text(&TextChunk::compressed("Title", "Two dice")).unwrap();
+        let texts: Vec<TextChunk> = png.texts().into_iter().map(Result::unwrap).collect();
+        assert_eq!(texts, vec![TextChunk::compressed("Title", "Two dice"), TextChunk::new("Author", "Me")]);
+        assert!(png.validate().is_empty());
+        assert_eq!(png.remove_texts("Title").unwrap().len(), 1);
+        assert!(png.remove_texts("Title").is_err());
+        assert_eq!(png.texts().into_iter().map(Result::unwrap).collect::<Vec<_>>(), vec![TextChunk::new("Author", "Me")]);
+    }
+
+    #[test]
+    fn test_png_malformed_text() {
+        let mut png = Png::try_from(PNG_FILE).unwrap();
+        png.set_text(&TextChunk::new("Author", "Me")).unwrap();
+        png.insert_before_iend(Chunk::new(ChunkType::from_str("zTXt").unwrap(), b"Title\0\0garbage".to_vec()));
+        let texts = png.texts();
+        assert_eq!(texts.len(), 2);
+        assert_eq!(texts[0].as_ref().unwrap(), &TextChunk::new("Author", "Me"));
+        assert!(matches!(texts[1], Err(PngyinxError::Text(DecompressionFailed))));
+
+        assert_eq!(png.remove_texts("Title").unwrap().len(), 1);
+        assert_eq!(png.texts().len(), 1);
+    }
+}
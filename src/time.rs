@@ -0,0 +1,194 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::time::TimeError::{InvalidField, WrongLength};
+use crate::Result;
+
+pub const TIME_CHUNK_TYPE: &str = "tIME";
+const LENGTH: usize = 7;
+
+/// The time of the last modification of the image, from tIME, always in UTC.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ImageTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    /// Up to 60 to allow for leap seconds.
+    pub second: u8,
+}
+
+impl Display for ImageTime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+            self.year, self.month, self.day, self.hour, self.minute, self.second,
+        )
+    }
+}
+
+impl ImageTime {
+    /// Builds a time after checking each field.
+    pub fn new(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> Result<ImageTime> {
+        let time = ImageTime { year, month, day, hour, minute, second };
+        time.check()?;
+        Ok(time)
+    }
+
+    pub fn now() -> ImageTime {
+        let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs());
+        ImageTime::from_unix(seconds)
+    }
+
+    /// Converts seconds since the Unix epoch to a calendar date and time.
+    pub fn from_unix(seconds: u64) -> ImageTime {
+        let days = (seconds / 86400) as i64;
+        let seconds = seconds % 86400;
+        // Days to civil date, shifting the year to start in March so that leap days come last.
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let day_of_era = z - era * 146097;
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+        let year = year_of_era + era * 400 + (month <= 2) as i64;
+        ImageTime {
+            year: year as u16,
+            month: month as u8,
+            day: day as u8,
+            hour: (seconds / 3600) as u8,
+            minute: (seconds / 60 % 60) as u8,
+            second: (seconds % 60) as u8,
+        }
+    }
+
+    pub fn from_data(data: &[u8]) -> Result<ImageTime> {
+        if data.len() != LENGTH {
+            return Err(WrongLength(data.len()).into());
+        }
+        let year = u16::from_be_bytes([data[0], data[1]]);
+        ImageTime::new(year, data[2], data[3], data[4], data[5], data[6])
+    }
+
+    /// Checks that the fields make a real date and time.
+    pub fn check(&self) -> Result<()> {
+        if !(1..=12).contains(&self.month) {
+            return Err(InvalidField("month", self.month as u16).into());
+        }
+        if self.day == 0 || self.day > days_in_month(self.year, self.month) {
+            return Err(InvalidField("day", self.day as u16).into());
+        }
+        if self.hour > 23 {
+            return Err(InvalidField("hour", self.hour as u16).into());
+        }
+        if self.minute > 59 {
+            return Err(InvalidField("minute", self.minute as u16).into());
+        }
+        if self.second > 60 {
+            return Err(InvalidField("second", self.second as u16).into());
+        }
+        Ok(())
+    }
+
+    pub fn to_data(&self) -> Vec<u8> {
+        let mut data = self.year.to_be_bytes().to_vec();
+        data.extend_from_slice(&[self.month, self.day, self.hour, self.minute, self.second]);
+        data
+    }
+
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        self.check()?;
+        Ok(Chunk::new(ChunkType::from_str(TIME_CHUNK_TYPE)?, self.to_data()))
+    }
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[derive(Debug)]
+pub enum TimeError {
+    WrongLength(usize),
+    InvalidField(&'static str, u16),
+}
+
+impl Display for TimeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WrongLength(length) => write!(f, "tIME must be 7 bytes long, not {}", length),
+            InvalidField(field, value) => write!(f, "{} isn't a valid {}", value, field),
+        }
+    }
+}
+
+impl TimeError {
+    /// A stable identifier for the error, for machine-readable output.
+    pub fn code(&self) -> &'static str {
+        match self {
+            WrongLength(_) => "wrong_length",
+            InvalidField(_, _) => "invalid_field",
+        }
+    }
+}
+
+impl std::error::Error for TimeError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::Png;
+    use crate::PngyinxError;
+
+    const PNG_FILE: &[u8] = include_bytes!("../pictures/dice.png");
+
+    #[test]
+    fn test_from_unix() {
+        assert_eq!(ImageTime::from_unix(0), ImageTime::new(1970, 1, 1, 0, 0, 0).unwrap());
+        assert_eq!(ImageTime::from_unix(951_782_400), ImageTime::new(2000, 2, 29, 0, 0, 0).unwrap());
+        assert_eq!(ImageTime::from_unix(1_792_367_999), ImageTime::new(2026, 10, 18, 23, 59, 59).unwrap());
+        assert!(ImageTime::now().check().is_ok());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let time = ImageTime::new(2024, 2, 29, 13, 37, 60).unwrap();
+        let chunk = time.to_chunk().unwrap();
+        assert_eq!(chunk.data(), &[0x07, 0xe8, 2, 29, 13, 37, 60]);
+        assert_eq!(ImageTime::from_data(chunk.data()).unwrap(), time);
+        assert_eq!(time.to_string(), "2024-02-29 13:37:60 UTC");
+    }
+
+    #[test]
+    fn test_invalid_times() {
+        assert!(matches!(ImageTime::new(2023, 2, 29, 0, 0, 0), Err(PngyinxError::Time(InvalidField("day", 29)))));
+        assert!(matches!(ImageTime::new(1900, 2, 29, 0, 0, 0), Err(PngyinxError::Time(InvalidField("day", 29)))));
+        assert!(matches!(ImageTime::new(2024, 13, 1, 0, 0, 0), Err(PngyinxError::Time(InvalidField("month", 13)))));
+        assert!(matches!(ImageTime::new(2024, 4, 31, 0, 0, 0), Err(PngyinxError::Time(InvalidField("day", 31)))));
+        assert!(matches!(ImageTime::new(2024, 1, 1, 24, 0, 0), Err(PngyinxError::Time(InvalidField("hour", 24)))));
+        assert!(matches!(ImageTime::from_data(&[0; 6]), Err(PngyinxError::Time(WrongLength(6)))));
+    }
+
+    #[test]
+    fn test_png_time() {
+        let mut png = Png::try_from(PNG_FILE).unwrap();
+        assert_eq!(png.time().unwrap(), None);
+        let time = ImageTime::new(2024, 1, 1, 0, 0, 0).unwrap();
+        png.set_time(&time).unwrap();
+        png.set_time(&ImageTime::new(2025, 1, 1, 0, 0, 0).unwrap()).unwrap();
+        assert_eq!(png.time().unwrap().unwrap().year, 2025);
+        assert_eq!(png.chunks_by_type(TIME_CHUNK_TYPE).len(), 1);
+        assert!(png.validate().is_empty());
+    }
+}
@@ -0,0 +1,228 @@
+use std::fmt::{Display, Formatter};
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::validation::Violation::{ChunkAfterIend, DuplicateChunk, IdatNotConsecutive, IhdrNotFirst, MissingIdat, MissingIend, MissingIhdr, PlteAfterIdat, ReservedBitSet, SrgbWithIccp};
+
+/// Chunks that the specification allows at most once per file.
+pub const UNIQUE_CHUNKS: [&str; 17] = [
+    "IHDR", "PLTE", "IEND", "cHRM", "cICP", "gAMA", "iCCP", "mDCV", "cLLI", "sBIT", "sRGB",
+    "bKGD", "hIST", "tRNS", "eXIf", "pHYs", "tIME",
+];
+
+/// A breach of the chunk ordering rules of the png specification.
+/// The `usize` is the index of the offending chunk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    MissingIhdr,
+    IhdrNotFirst(usize),
+    MissingIend,
+    ChunkAfterIend(usize, ChunkType),
+    MissingIdat,
+    IdatNotConsecutive(usize),
+    PlteAfterIdat(usize),
+    DuplicateChunk(usize, ChunkType),
+    ReservedBitSet(usize, ChunkType),
+    SrgbWithIccp(usize),
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MissingIhdr => write!(f, "There is no IHDR chunk"),
+            IhdrNotFirst(index) => write!(f, "Chunk {}: IHDR must be the first chunk", index),
+            MissingIend => write!(f, "There is no IEND chunk"),
+            ChunkAfterIend(index, chunk_type) => write!(f, "Chunk {}: {} comes after IEND", index, chunk_type),
+            MissingIdat => write!(f, "There is no IDAT chunk"),
+            IdatNotConsecutive(index) => write!(f, "Chunk {}: IDAT chunks must be consecutive", index),
+            PlteAfterIdat(index) => write!(f, "Chunk {}: PLTE must come before the first IDAT", index),
+            DuplicateChunk(index, chunk_type) => write!(f, "Chunk {}: {} may only appear once", index, chunk_type),
+            ReservedBitSet(index, chunk_type) => write!(f, "Chunk {}: {} has its reserved bit set", index, chunk_type),
+            SrgbWithIccp(index) => write!(f, "Chunk {}: sRGB and iCCP must not both be present", index),
+        }
+    }
+}
+
+impl Violation {
+    /// A stable identifier for the violation, for machine-readable output.
+    pub fn code(&self) -> &'static str {
+        match self {
+            MissingIhdr => "missing_ihdr",
+            IhdrNotFirst(_) => "ihdr_not_first",
+            MissingIend => "missing_iend",
+            ChunkAfterIend(_, _) => "chunk_after_iend",
+            MissingIdat => "missing_idat",
+            IdatNotConsecutive(_) => "idat_not_consecutive",
+            PlteAfterIdat(_) => "plte_after_idat",
+            DuplicateChunk(_, _) => "duplicate_chunk",
+            ReservedBitSet(_, _) => "reserved_bit_set",
+            SrgbWithIccp(_) => "srgb_with_iccp",
+        }
+    }
+
+    /// The index of the offending chunk, if there is one.
+    pub fn index(&self) -> Option<usize> {
+        match self {
+            MissingIhdr | MissingIend | MissingIdat => None,
+            IhdrNotFirst(index) | IdatNotConsecutive(index) | PlteAfterIdat(index) | SrgbWithIccp(index) => Some(*index),
+            ChunkAfterIend(index, _) | DuplicateChunk(index, _) | ReservedBitSet(index, _) => Some(*index),
+        }
+    }
+}
+
+/// Serialized as `{"code", "index", "message"}`, `index` being null for missing chunks.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Violation {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("Violation", 3)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("index", &self.index())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+/// Checks `chunks` against the ordering rules of the specification and returns every violation found.
+pub fn validate(chunks: &[Chunk]) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let mut seen: Vec<&ChunkType> = Vec::new();
+    let mut iend = None;
+    let mut first_idat = None;
+    let mut last_idat = None;
+
+    if chunks.is_empty() {
+        return vec![MissingIhdr, MissingIdat, MissingIend];
+    }
+    if chunks.iter().all(|chunk| !is(chunk, "IHDR")) {
+        violations.push(MissingIhdr);
+    }
+
+    for (index, chunk) in chunks.iter().enumerate() {
+        let chunk_type = chunk.chunk_type();
+        if iend.is_some() {
+            violations.push(ChunkAfterIend(index, *chunk_type));
+        }
+        if !chunk_type.is_reserved_bit_valid() {
+            violations.push(ReservedBitSet(index, *chunk_type));
+        }
+        if UNIQUE_CHUNKS.iter().any(|unique| unique.as_bytes() == chunk_type.bytes())
+            && seen.contains(&chunk_type) {
+            violations.push(DuplicateChunk(index, *chunk_type));
+        }
+        if is(chunk, "IHDR") && index != 0 {
+            violations.push(IhdrNotFirst(index));
+        }
+        if is(chunk, "PLTE") && first_idat.is_some() {
+            violations.push(PlteAfterIdat(index));
+        }
+        if is(chunk, "IDAT") {
+            if last_idat.is_some_and(|last| last + 1 != index) {
+                violations.push(IdatNotConsecutive(index));
+            }
+            first_idat.get_or_insert(index);
+            last_idat = Some(index);
+        }
+        if is(chunk, "IEND") {
+            iend.get_or_insert(index);
+        }
+        let seen_type = |other: &str| seen.iter().any(|seen| seen.bytes() == other.as_bytes());
+        if (is(chunk, "sRGB") && seen_type("iCCP")) || (is(chunk, "iCCP") && seen_type("sRGB")) {
+            violations.push(SrgbWithIccp(index));
+        }
+        seen.push(chunk_type);
+    }
+
+    if first_idat.is_none() {
+        violations.push(MissingIdat);
+    }
+    if iend.is_none() {
+        violations.push(MissingIend);
+    }
+    violations
+}
+
+fn is(chunk: &Chunk, chunk_type: &str) -> bool {
+    chunk.chunk_type().bytes() == chunk_type.as_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::Png;
+    use std::str::FromStr;
+
+    const PNG_FILE: &[u8] = include_bytes!("../pictures/dice.png");
+
+    fn chunk(chunk_type: &str) -> Chunk {
+        Chunk::new(ChunkType::from_str(chunk_type).unwrap(), Vec::new())
+    }
+
+    fn chunks(chunk_types: &[&str]) -> Vec<Chunk> {
+        chunk_types.iter().map(|chunk_type| chunk(chunk_type)).collect()
+    }
+
+    #[test]
+    fn test_violation_code_and_index() {
+        let violations = validate(&chunks(&["IHDR", "IEND", "IHDR"]));
+        let codes: Vec<(&str, Option<usize>)> = violations.iter().map(|violation| (violation.code(), violation.index())).collect();
+        assert_eq!(codes, vec![
+            ("chunk_after_iend", Some(2)),
+            ("duplicate_chunk", Some(2)),
+            ("ihdr_not_first", Some(2)),
+            ("missing_idat", None),
+        ]);
+    }
+
+    #[test]
+    fn test_valid_png() {
+        let png = Png::try_from(PNG_FILE).unwrap();
+        assert!(validate(png.chunks()).is_empty());
+    }
+
+    #[test]
+    fn test_empty_png() {
+        assert_eq!(validate(&[]), vec![MissingIhdr, MissingIdat, MissingIend]);
+    }
+
+    #[test]
+    fn test_ihdr_not_first() {
+        let violations = validate(&chunks(&["gAMA", "IHDR", "IDAT", "IEND"]));
+        assert_eq!(violations, vec![IhdrNotFirst(1)]);
+    }
+
+    #[test]
+    fn test_chunk_after_iend() {
+        let violations = validate(&chunks(&["IHDR", "IDAT", "IEND", "ruSt"]));
+        assert_eq!(violations, vec![ChunkAfterIend(3, ChunkType::from_str("ruSt").unwrap())]);
+    }
+
+    #[test]
+    fn test_idat_not_consecutive() {
+        let violations = validate(&chunks(&["IHDR", "IDAT", "tEXt", "IDAT", "IEND"]));
+        assert_eq!(violations, vec![IdatNotConsecutive(3)]);
+    }
+
+    #[test]
+    fn test_plte_after_idat() {
+        let violations = validate(&chunks(&["IHDR", "IDAT", "PLTE", "IEND"]));
+        assert_eq!(violations, vec![PlteAfterIdat(2)]);
+    }
+
+    #[test]
+    fn test_duplicate_chunk() {
+        let violations = validate(&chunks(&["IHDR", "gAMA", "gAMA", "IDAT", "tEXt", "tEXt", "IEND"]));
+        assert_eq!(violations, vec![DuplicateChunk(2, ChunkType::from_str("gAMA").unwrap())]);
+    }
+
+    #[test]
+    fn test_srgb_with_iccp() {
+        let violations = validate(&chunks(&["IHDR", "iCCP", "gAMA", "sRGB", "IDAT", "IEND"]));
+        assert_eq!(violations, vec![SrgbWithIccp(3)]);
+    }
+
+    #[test]
+    fn test_reserved_bit_set() {
+        let violations = validate(&chunks(&["IHDR", "IDAT", "ruat", "IEND"]));
+        assert_eq!(violations, vec![ReservedBitSet(2, ChunkType::from_str("ruat").unwrap())]);
+    }
+}
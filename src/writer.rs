@@ -0,0 +1,60 @@
+use std::io::Write;
+use crate::chunk::Chunk;
+use crate::png::Png;
+use crate::Result;
+
+/// Writes a png to any `Write` sink, one chunk at a time.
+///
+/// The signature is written by `new`; chunks are then streamed straight to the sink
+/// without building the whole file in memory.
+pub struct PngWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> PngWriter<W> {
+    pub fn new(mut writer: W) -> Result<Self> {
+        writer.write_all(&Png::STANDARD_HEADER)?;
+        Ok(Self {
+            writer
+        })
+    }
+
+    pub fn write_chunk(&mut self, chunk: &Chunk) -> Result<()> {
+        chunk.write_to(&mut self.writer)
+    }
+
+    pub fn write_chunks<'a>(&mut self, chunks: impl IntoIterator<Item = &'a Chunk>) -> Result<()> {
+        for chunk in chunks {
+            self.write_chunk(chunk)?;
+        }
+        Ok(())
+    }
+
+    /// Flushes the sink and gives it back.
+    pub fn finish(mut self) -> Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG_FILE: &[u8] = include_bytes!("../pictures/dice.png");
+
+    #[test]
+    fn test_write_png() {
+        let png = Png::try_from(PNG_FILE).unwrap();
+        let mut writer = PngWriter::new(Vec::new()).unwrap();
+        writer.write_chunks(png.chunks()).unwrap();
+        let bytes = writer.finish().unwrap();
+        assert_eq!(bytes, PNG_FILE);
+    }
+
+    #[test]
+    fn test_write_signature_only() {
+        let bytes = PngWriter::new(Vec::new()).unwrap().finish().unwrap();
+        assert_eq!(bytes, Png::STANDARD_HEADER);
+    }
+}